@@ -62,6 +62,21 @@ if __name__ == "__main__":
     parser = argparse.ArgumentParser(description="Tether Backend API Server")
     parser.add_argument("--port", type=int, default=8000, help="Port to run the server on")
     parser.add_argument("--host", type=str, default="127.0.0.1", help="Host to bind to")
+    parser.add_argument("--log-file", type=str, default=None, help="Also write logs to this file")
     args = parser.parse_args()
 
-    uvicorn.run(app, host=args.host, port=args.port)
+    log_config = uvicorn.config.LOGGING_CONFIG
+    if args.log_file:
+        from copy import deepcopy
+
+        log_config = deepcopy(log_config)
+        for name, formatter in (("file", "default"), ("file_access", "access")):
+            log_config["handlers"][name] = {
+                "class": "logging.FileHandler",
+                "filename": args.log_file,
+                "formatter": formatter,
+            }
+        log_config["loggers"]["uvicorn"]["handlers"].append("file")
+        log_config["loggers"]["uvicorn.access"]["handlers"].append("file_access")
+
+    uvicorn.run(app, host=args.host, port=args.port, log_config=log_config)
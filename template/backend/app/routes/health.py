@@ -2,6 +2,9 @@
 Health check endpoint.
 """
 
+import logging
+from typing import Literal
+
 from fastapi import APIRouter, Request
 from pydantic import BaseModel
 
@@ -12,6 +15,14 @@ class HealthResponse(BaseModel):
     version: str
 
 
+class VersionResponse(BaseModel):
+    version: str
+
+
+class LogLevel(BaseModel):
+    level: Literal["debug", "info", "warning", "error", "critical"]
+
+
 router = APIRouter()
 
 
@@ -26,3 +37,18 @@ async def health(request: Request) -> HealthResponse:
         model_loaded=model_loaded,
         version="0.1.0",
     )
+
+
+@router.get("/version", response_model=VersionResponse)
+async def version() -> VersionResponse:
+    """Backend version, checked by the desktop shell at startup."""
+    return VersionResponse(version="0.1.0")
+
+
+@router.post("/log-level", response_model=LogLevel)
+async def set_log_level(body: LogLevel) -> LogLevel:
+    """Change log verbosity without a restart, used by the desktop shell."""
+    level = getattr(logging, body.level.upper())
+    for name in ("", "uvicorn", "uvicorn.error", "uvicorn.access", "app"):
+        logging.getLogger(name).setLevel(level)
+    return body
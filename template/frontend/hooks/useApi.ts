@@ -75,7 +75,7 @@ const RETRY_DELAY = 1000;
 const REQUEST_TIMEOUT = 120000; // 2 minutes for thinking models
 const HEALTH_CHECK_INTERVAL = 10000; // Check health every 10 seconds
 
-// Get the API port from Tauri
+// Get the API port from Tauri (fails while the backend isn't running)
 async function getApiUrl(): Promise<string> {
   try {
     const port = await invoke<number>("get_api_port");
@@ -141,11 +141,10 @@ async function sendApiKey(apiKey: string): Promise<SetApiKeyResponse> {
 }
 
 async function waitForBackend(): Promise<boolean> {
-  // First, get the correct API URL from Tauri
-  await getApiUrl();
-
   for (let i = 0; i < MAX_RETRIES; i++) {
     try {
+      // Re-read the port each attempt: it is only available once running
+      await getApiUrl();
       const health = await checkHealth();
       if (health.status === "healthy") {
         return true;
@@ -0,0 +1,230 @@
+//! Default wiring of the sidecar into a Tauri app.
+//!
+//! [`builder`] is all most apps need, or the [`plugin`](crate::plugin) for
+//! apps that already have a builder. Apps with their own commands or window
+//! handling build on the pieces instead: create the manager, pass it to
+//! [`manage`] in `setup`, then [`start_in_background`], and forward window
+//! events to [`on_window_event`]. The `_with` variants also take a closure
+//! that configures the [`SidecarRegistry`], e.g. to add more sidecars.
+
+use crate::commands;
+use crate::config_watch;
+use crate::error::SidecarError;
+use crate::metrics;
+use crate::monitor;
+use crate::registry::SidecarRegistry;
+use crate::sidecar::SidecarManager;
+use crate::signals;
+use log::error;
+use std::sync::Arc;
+use tauri::{AppHandle, CloseRequestApi, Manager, Window, WindowEvent, Wry};
+use tokio::sync::Mutex;
+
+/// Port of a backend started outside the app (e.g. `pnpm dev:py`).
+///
+/// Only honoured in debug builds, via `TETHER_EXTERNAL_API_PORT`.
+fn external_backend_port() -> Option<u16> {
+    if !cfg!(debug_assertions) {
+        return None;
+    }
+    std::env::var("TETHER_EXTERNAL_API_PORT").ok()?.parse().ok()
+}
+
+/// The manager for the app's sidecar, on a free port and with `TETHER_*`
+/// overrides applied before `configure`. In debug builds with
+/// `TETHER_EXTERNAL_API_PORT` set it attaches to that backend instead, and
+/// `configure` is skipped.
+pub fn default_manager(
+    configure: impl FnOnce(SidecarManager) -> SidecarManager,
+) -> Result<SidecarManager, SidecarError> {
+    if let Some(port) = external_backend_port() {
+        return Ok(SidecarManager::external(port));
+    }
+    let port = portpicker::pick_unused_port().ok_or("No available port")?;
+    Ok(configure(SidecarManager::from_env(port)?))
+}
+
+/// Put `manager` in app state, along with its [`PortReader`],
+/// [`RequestGate`] and a [`SidecarRegistry`] holding it under its
+/// [name](SidecarManager::with_name), and start the background tasks
+/// it is configured for (signal forwarding, health monitor, config
+/// watcher, metrics endpoint). Does not start the sidecar.
+///
+/// Call it from `setup`, not from async code: it locks each sidecar once
+/// to read its configuration.
+///
+/// [`PortReader`]: crate::sidecar::PortReader
+/// [`RequestGate`]: crate::proxy::RequestGate
+pub fn manage(app: &AppHandle, manager: SidecarManager) -> Arc<Mutex<SidecarManager>> {
+    let name = manager.name().to_string();
+    let manager = Arc::new(Mutex::new(manager));
+    manage_registry(
+        app,
+        manager.clone(),
+        SidecarRegistry::new().with_sidecar(name, manager.clone()),
+    );
+    manager
+}
+
+/// [`manage`], passing the registry through `configure` before it is
+/// put in app state, e.g. to keep the sidecars alive on close, change the
+/// shutdown timeouts or stop order, or add more sidecars and their
+/// dependencies. `manager` stays the one the [`commands`] control; the
+/// others are reached through the registry. Every sidecar in the registry
+/// gets its background tasks.
+pub fn manage_with(
+    app: &AppHandle,
+    manager: SidecarManager,
+    configure: impl FnOnce(SidecarRegistry) -> Result<SidecarRegistry, SidecarError>,
+) -> Result<Arc<Mutex<SidecarManager>>, SidecarError> {
+    let name = manager.name().to_string();
+    let manager = Arc::new(Mutex::new(manager));
+    let registry = configure(SidecarRegistry::new().with_sidecar(name, manager.clone()))?;
+    manage_registry(app, manager.clone(), registry);
+    Ok(manager)
+}
+
+/// Put `primary` and `registry` in app state and start the background
+/// tasks of every sidecar in the registry.
+fn manage_registry(
+    app: &AppHandle,
+    primary: Arc<Mutex<SidecarManager>>,
+    registry: SidecarRegistry,
+) {
+    let mut forward_signals = false;
+    for name in registry.names() {
+        let Some(sidecar) = registry.get(name) else {
+            continue;
+        };
+        let mut manager = tauri::async_runtime::block_on(sidecar.lock());
+        manager.initialize(app);
+        forward_signals |= manager.forwards_signals();
+        if Arc::ptr_eq(sidecar, &primary) {
+            app.manage(manager.port_reader());
+            app.manage(manager.request_gate());
+        }
+
+        // Watch the backend's health in the background
+        if manager.monitor_enabled() {
+            monitor::spawn_health_monitor(sidecar.clone());
+        }
+
+        // Restart or reload the backend when its config changes
+        if let Some(watch) = manager.config_watch().cloned() {
+            config_watch::spawn_config_watcher(sidecar.clone(), watch);
+        }
+
+        // Let Prometheus scrape the manager's own metrics
+        if let Some(port) = manager.metrics_port() {
+            metrics::spawn_metrics_server(sidecar.clone(), port);
+        }
+    }
+
+    app.manage(primary);
+    app.manage(registry);
+
+    // Stop the backends gracefully if the app itself is terminated
+    if forward_signals {
+        signals::forward_to_sidecars(app.clone());
+    }
+}
+
+/// Start the sidecar without blocking setup, logging a failure. To decide
+/// at runtime instead (e.g. from a user setting), skip this and call the
+/// `start_backend` command later; [`builder`] and the plugin skip it for
+/// managers built [`with_manual_start`](SidecarManager::with_manual_start).
+pub fn start_in_background(app: &AppHandle, manager: Arc<Mutex<SidecarManager>>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut manager = manager.lock().await;
+        if let Err(e) = manager.start(&app).await {
+            error!("Failed to start API server: {}", e);
+        }
+    });
+}
+
+/// [`start_in_background`] for every sidecar in the app's
+/// [`SidecarRegistry`], dependencies first, as
+/// [`start_all`](SidecarRegistry::start_all) does.
+pub fn start_all_in_background(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let registry = app.state::<SidecarRegistry>();
+        if let Err(e) = registry.start_all(&app).await {
+            error!("{}", e);
+        }
+    });
+}
+
+/// Stop the sidecars when the window closes, or hide the window if the
+/// registry keeps them alive on close.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let WindowEvent::CloseRequested { api, .. } = event else {
+        return;
+    };
+    let app_handle = window.app_handle().clone();
+    if keep_alive_on_close(&app_handle, api, || window.hide()) {
+        return;
+    }
+
+    let registry = app_handle.state::<SidecarRegistry>();
+    tauri::async_runtime::block_on(async {
+        if let Err(e) = registry.shutdown().await {
+            error!("{}", e);
+        }
+    });
+}
+
+/// Keep a window's close request from stopping the sidecars if the
+/// registry [keeps them alive](SidecarRegistry::with_keep_alive_on_close),
+/// hiding the window with `hide` instead. Returns whether it did.
+pub(crate) fn keep_alive_on_close(
+    app: &AppHandle,
+    api: &CloseRequestApi,
+    hide: impl FnOnce() -> tauri::Result<()>,
+) -> bool {
+    // Tray-style apps keep the backend until quit_app
+    if !app.state::<SidecarRegistry>().keep_alive_on_close() {
+        return false;
+    }
+    api.prevent_close();
+    if let Err(e) = hide() {
+        error!("Failed to hide window: {}", e);
+    }
+    true
+}
+
+/// A Tauri builder with the shell plugin, a sidecar manager from
+/// [`default_manager`] passed through `configure`, the
+/// [`commands`](crate::commands) and shutdown on close, which starts the
+/// sidecar once the app is set up.
+pub fn builder(
+    configure: impl FnOnce(SidecarManager) -> SidecarManager + Send + 'static,
+) -> tauri::Builder<Wry> {
+    builder_with(configure, Ok)
+}
+
+/// [`builder`], with the registry set up by [`manage_with`] and
+/// `configure_registry`. Every sidecar in it is started once the app is
+/// set up, unless the one from `configure` is built
+/// [`with_manual_start`](SidecarManager::with_manual_start).
+pub fn builder_with(
+    configure: impl FnOnce(SidecarManager) -> SidecarManager + Send + 'static,
+    configure_registry: impl FnOnce(SidecarRegistry) -> Result<SidecarRegistry, SidecarError>
+        + Send
+        + 'static,
+) -> tauri::Builder<Wry> {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            let manager = default_manager(configure)?;
+            let autostart = !manager.manual_start();
+            manage_with(app.handle(), manager, configure_registry)?;
+            if autostart {
+                start_all_in_background(app.handle());
+            }
+            Ok(())
+        })
+        .on_window_event(on_window_event)
+        .invoke_handler(commands::handler())
+}
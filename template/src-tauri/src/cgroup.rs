@@ -0,0 +1,92 @@
+//! Confining the sidecar to a cgroup v2 on Linux, see
+//! [`SidecarManager::with_cgroup`](crate::sidecar::SidecarManager::with_cgroup).
+
+#[cfg(target_os = "linux")]
+use log::info;
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+
+/// Resource limits written to the sidecar's cgroup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CgroupLimits {
+    /// `memory.max` in bytes.
+    pub memory_max: Option<u64>,
+    /// `cpu.max` as quota and period in microseconds, e.g.
+    /// `(50_000, 100_000)` for half a CPU.
+    pub cpu_max: Option<(u64, u64)>,
+}
+
+/// A cgroup holding a sidecar process. Removed again on drop if we created
+/// it, which only succeeds once the process has exited.
+pub(crate) struct Cgroup {
+    path: PathBuf,
+    created: bool,
+}
+
+impl Cgroup {
+    /// Move `pid` into the cgroup at `path`, creating it if needed, and
+    /// apply `limits`. Best effort: returns `None` after logging a warning
+    /// where cgroups v2 aren't available or we lack permission.
+    pub fn join(path: &Path, limits: &CgroupLimits, pid: u32) -> Option<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            let created = !path.exists();
+            if created {
+                if let Err(e) = std::fs::create_dir(path) {
+                    warn!("Failed to create cgroup {}: {}", path.display(), e);
+                    return None;
+                }
+            }
+            let cgroup = Self {
+                path: path.to_path_buf(),
+                created,
+            };
+            // The limit files only exist once the parent delegates the
+            // controllers; this fails harmlessly if they already are
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::write(parent.join("cgroup.subtree_control"), "+memory +cpu");
+            }
+            if let Some(bytes) = limits.memory_max {
+                cgroup.write("memory.max", &bytes.to_string());
+            }
+            if let Some((quota, period)) = limits.cpu_max {
+                cgroup.write("cpu.max", &format!("{} {}", quota, period));
+            }
+            if !cgroup.write("cgroup.procs", &pid.to_string()) {
+                return None;
+            }
+            info!("Placed API server in cgroup {}", path.display());
+            Some(cgroup)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (path, limits, pid);
+            warn!("cgroups are only available on Linux, not limiting the API server");
+            None
+        }
+    }
+
+    /// Write `value` to the cgroup's `file`. Returns whether it worked.
+    #[cfg(target_os = "linux")]
+    fn write(&self, file: &str, value: &str) -> bool {
+        let path = self.path.join(file);
+        match std::fs::write(&path, value) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to write {}: {}", path.display(), e);
+                false
+            }
+        }
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        if self.created {
+            if let Err(e) = std::fs::remove_dir(&self.path) {
+                debug!("Failed to remove cgroup {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,553 @@
+//! The Tauri commands the template's frontend calls.
+//!
+//! [`handler`] registers all of them; apps that add commands of their own
+//! list these alongside theirs in `tauri::generate_handler!` instead.
+
+use crate::logs::{LogEntry, LogPage, LogStream, RecentLogs, MAX_PAGE_LINES};
+use crate::proxy::{self, Ping, ProxyRequest, ProxyResponse, RequestGate};
+use crate::registry::{SidecarInfo, SidecarRegistry};
+use crate::resolve::SidecarCandidate;
+#[cfg(debug_assertions)]
+use crate::sidecar::ShutdownReport;
+use crate::sidecar::{
+    self, AutoRestartInfo, BackendMetrics, BackendStatus, BinaryInfo, EventNames, PortReader,
+    RestartBackoff, RestartOutcome, RunOutcome, SidecarConfig, SidecarManager, StackDump,
+    StatusTransition, ValidationReport,
+};
+use log::error;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tokio::sync::Mutex;
+
+#[tauri::command]
+pub async fn get_api_port(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    port: tauri::State<'_, PortReader>,
+) -> Result<u16, String> {
+    let mut manager = match state.try_lock() {
+        Ok(manager) => manager,
+        // Don't queue behind a restart holding the lock, the port is known
+        Err(_) if port.restarting() => return Ok(port.get()),
+        Err(_) => state.lock().await,
+    };
+    manager.wake().await.map_err(|e| e.to_string())?;
+    manager.running_port().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_named_ports(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<HashMap<String, u16>, String> {
+    let manager = state.lock().await;
+    Ok(manager.named_ports())
+}
+
+#[tauri::command]
+pub async fn wait_for_named_port(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    name: String,
+    timeout_ms: u64,
+) -> Result<u16, String> {
+    let port = state
+        .lock()
+        .await
+        .port_named(&name)
+        .ok_or_else(|| format!("No port named {}", name))?;
+    sidecar::wait_for_port(port, Duration::from_millis(timeout_ms))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(port)
+}
+
+#[tauri::command]
+pub async fn get_backend_status(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<BackendStatus, String> {
+    let manager = state.lock().await;
+    Ok(manager.status())
+}
+
+#[tauri::command]
+pub async fn get_status_history(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<Vec<StatusTransition>, String> {
+    let manager = state.lock().await;
+    Ok(manager.status_history())
+}
+
+#[tauri::command]
+pub async fn get_backend_pid(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<Option<u32>, String> {
+    let manager = state.lock().await;
+    Ok(manager.pid())
+}
+
+#[tauri::command]
+pub async fn get_backend_metrics(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<BackendMetrics, String> {
+    let mut manager = state.lock().await;
+    Ok(manager.metrics().await)
+}
+
+#[tauri::command]
+pub async fn get_auto_restart(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<Option<AutoRestartInfo>, String> {
+    let manager = state.lock().await;
+    Ok(manager.auto_restart_info())
+}
+
+#[tauri::command]
+pub async fn set_restart_backoff(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    backoff: RestartBackoff,
+) -> Result<(), String> {
+    let mut manager = state.lock().await;
+    manager
+        .set_restart_backoff(backoff)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_maintenance_mode(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    enabled: bool,
+) -> Result<(), String> {
+    state.lock().await.set_maintenance_mode(enabled).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_auto_restart(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut manager = state.lock().await;
+    manager.set_auto_restart(enabled).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_startup_timeout_ms(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<u64, String> {
+    let manager = state.lock().await;
+    Ok(manager.startup_timeout().as_millis() as u64)
+}
+
+#[tauri::command]
+pub async fn set_startup_timeout_ms(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    timeout_ms: u64,
+) -> Result<(), String> {
+    let mut manager = state.lock().await;
+    manager
+        .set_startup_timeout(Duration::from_millis(timeout_ms))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_event_names(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<EventNames, String> {
+    let manager = state.lock().await;
+    Ok(manager.event_names().clone())
+}
+
+#[tauri::command]
+pub async fn get_recent_logs(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    limit: Option<usize>,
+    stream: Option<LogStream>,
+    flat: Option<bool>,
+) -> Result<RecentLogs, String> {
+    let manager = state.lock().await;
+    Ok(manager.recent_logs(limit, stream, flat.unwrap_or(false)))
+}
+
+#[tauri::command]
+pub async fn get_sidecar_logs(
+    registry: tauri::State<'_, SidecarRegistry>,
+    name: String,
+    limit: Option<usize>,
+    stream: Option<LogStream>,
+    flat: Option<bool>,
+) -> Result<RecentLogs, String> {
+    let manager = registry
+        .get(&name)
+        .ok_or_else(|| format!("No sidecar named {:?} is registered", name))?
+        .lock()
+        .await;
+    Ok(manager.recent_logs(limit, stream, flat.unwrap_or(false)))
+}
+
+#[tauri::command]
+pub async fn search_logs(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    query: String,
+    case_insensitive: bool,
+    max_results: usize,
+) -> Result<Vec<LogEntry>, String> {
+    let manager = state.lock().await;
+    Ok(manager.search_logs(&query, case_insensitive, max_results))
+}
+
+#[tauri::command]
+pub async fn read_log_file(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    offset: Option<u64>,
+    limit: Option<usize>,
+    rotation: Option<u32>,
+) -> Result<LogPage, String> {
+    let manager = state.lock().await;
+    manager
+        .read_log_file(
+            rotation.unwrap_or(0),
+            offset.unwrap_or(0),
+            limit.unwrap_or(MAX_PAGE_LINES),
+        )
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rotate_logs(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<PathBuf, String> {
+    let manager = state.lock().await;
+    manager.rotate_log_file().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn debug_resolve_sidecar(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<Vec<SidecarCandidate>, String> {
+    let manager = state.lock().await;
+    Ok(manager.debug_resolve_sidecar(&app))
+}
+
+#[tauri::command]
+pub async fn switch_backend_binary(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    name: String,
+) -> Result<String, String> {
+    let mut manager = state.lock().await;
+    manager
+        .switch_binary(&name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_backend_config(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<SidecarConfig, String> {
+    let manager = state.lock().await;
+    Ok(manager.config())
+}
+
+#[tauri::command]
+pub async fn apply_backend_config(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    config: SidecarConfig,
+) -> Result<BackendStatus, String> {
+    let mut manager = state.lock().await;
+    manager
+        .apply_config(config)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn dump_backend_stack(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<StackDump, String> {
+    let manager = state.lock().await;
+    manager.dump_stack().await.map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "debug-commands")]
+#[tauri::command]
+pub async fn collect_backend_dump(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<PathBuf, String> {
+    let manager = state.lock().await;
+    manager.collect_dump().await.map_err(|e| e.to_string())
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn test_shutdown(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<ShutdownReport, String> {
+    let mut manager = state.lock().await;
+    manager.test_shutdown().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_sidecar_binary_info(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<BinaryInfo, String> {
+    let mut manager = state.lock().await;
+    manager.binary_info(&app).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn validate_backend(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<ValidationReport, String> {
+    let manager = state.lock().await;
+    Ok(manager.validate(&app))
+}
+
+#[tauri::command]
+pub async fn run_backend_once(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<RunOutcome, String> {
+    let mut manager = state.lock().await;
+    manager.run_once(&app).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn proxy_request(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    gate: tauri::State<'_, RequestGate>,
+    request: ProxyRequest,
+) -> Result<ProxyResponse, String> {
+    // Wait out a restart rather than failing with a connection error, and
+    // before locking the manager, which the restart holds
+    let _in_flight = match gate.inner().clone().pass().await {
+        Ok(in_flight) => in_flight,
+        Err(unavailable) => return Ok(unavailable),
+    };
+
+    // Don't hold the manager across the request, which may be slow
+    let (port, token, max_response_bytes) = {
+        let mut manager = state.lock().await;
+        manager.wake().await.map_err(|e| e.to_string())?;
+        let port = manager.running_port().map_err(|e| e.to_string())?;
+        let token = manager.auth_token().map(str::to_string);
+        (port, token, manager.proxy_max_response_bytes())
+    };
+    proxy::forward(port, token.as_deref(), max_response_bytes, &request).await
+}
+
+#[tauri::command]
+pub async fn get_api_token(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<Option<String>, String> {
+    let manager = state.lock().await;
+    Ok(manager.auth_token().map(str::to_string))
+}
+
+#[tauri::command]
+pub async fn rotate_api_token(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<(), String> {
+    let mut manager = state.lock().await;
+    manager.rotate_auth_token().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn ping(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    gate: tauri::State<'_, RequestGate>,
+) -> Result<Ping, String> {
+    let started = Instant::now();
+    let _in_flight = gate
+        .inner()
+        .clone()
+        .pass()
+        .await
+        .map_err(|unavailable| unavailable.body)?;
+
+    let (port, token, path, max_response_bytes) = {
+        let mut manager = state.lock().await;
+        manager.wake().await.map_err(|e| e.to_string())?;
+        let port = manager.running_port().map_err(|e| e.to_string())?;
+        let token = manager.auth_token().map(str::to_string);
+        let path = manager.ping_path().to_string();
+        (port, token, path, manager.proxy_max_response_bytes())
+    };
+    let request = ProxyRequest {
+        method: "GET".into(),
+        path,
+        headers: Vec::new(),
+        body: None,
+    };
+    let forwarding = Instant::now();
+    let (response, backend) =
+        proxy::forward_timed(port, token.as_deref(), max_response_bytes, &request).await?;
+    let forwarded = forwarding.elapsed();
+    Ok(Ping {
+        status: response.status,
+        total_us: started.elapsed().as_micros() as u64,
+        backend_us: backend.as_micros() as u64,
+        proxy_overhead_us: forwarded.saturating_sub(backend).as_micros() as u64,
+    })
+}
+
+#[tauri::command]
+pub async fn reload_backend_with_config(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    config: SidecarConfig,
+) -> Result<BackendStatus, String> {
+    let mut manager = state.lock().await;
+    manager.reload(config).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reload_backend_config(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<bool, String> {
+    let manager = state.lock().await;
+    manager.reload_config().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn write_backend_stdin(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    data: String,
+) -> Result<(), String> {
+    let mut manager = state.lock().await;
+    manager
+        .write_stdin(data.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn start_backend(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    env: Option<HashMap<String, String>>,
+    current_dir: Option<PathBuf>,
+) -> Result<String, String> {
+    let mut manager = state.lock().await;
+    for (key, value) in env.unwrap_or_default() {
+        manager.set_env(key, value);
+    }
+    if let Some(dir) = current_dir {
+        manager.set_current_dir(dir).map_err(|e| e.to_string())?;
+    }
+    manager.start(&app).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stop_backend(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<String, String> {
+    let mut manager = state.lock().await;
+    manager.stop_async().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_backend_log_level(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+    level: String,
+) -> Result<String, String> {
+    let mut manager = state.lock().await;
+    manager
+        .set_log_level(&level)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restart_backend(
+    state: tauri::State<'_, Arc<Mutex<SidecarManager>>>,
+) -> Result<RestartOutcome, String> {
+    let message = SidecarManager::user_restart_shared(&state)
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = state.lock().await.status();
+    Ok(RestartOutcome {
+        status: status.status,
+        port: status.port,
+        message,
+    })
+}
+
+#[tauri::command]
+pub async fn list_sidecars(
+    registry: tauri::State<'_, SidecarRegistry>,
+) -> Result<Vec<SidecarInfo>, String> {
+    Ok(registry.list().await)
+}
+
+#[tauri::command]
+pub async fn restart_all_backends(
+    app: tauri::AppHandle,
+    registry: tauri::State<'_, SidecarRegistry>,
+) -> Result<HashMap<String, RestartOutcome>, String> {
+    Ok(registry.restart_all(&app).await)
+}
+
+#[tauri::command]
+pub async fn quit_app(
+    app: tauri::AppHandle,
+    registry: tauri::State<'_, SidecarRegistry>,
+) -> Result<(), String> {
+    if let Err(e) = registry.shutdown().await {
+        error!("{}", e);
+    }
+    app.exit(0);
+    Ok(())
+}
+
+/// Invoke handler for every command in this module.
+pub fn handler() -> impl Fn(Invoke) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        get_api_port,
+        get_named_ports,
+        wait_for_named_port,
+        get_backend_status,
+        get_status_history,
+        get_backend_pid,
+        get_backend_metrics,
+        get_auto_restart,
+        set_auto_restart,
+        set_restart_backoff,
+        set_maintenance_mode,
+        get_startup_timeout_ms,
+        set_startup_timeout_ms,
+        get_event_names,
+        get_recent_logs,
+        get_sidecar_logs,
+        search_logs,
+        read_log_file,
+        rotate_logs,
+        debug_resolve_sidecar,
+        switch_backend_binary,
+        get_backend_config,
+        apply_backend_config,
+        get_sidecar_binary_info,
+        dump_backend_stack,
+        #[cfg(feature = "debug-commands")]
+        collect_backend_dump,
+        #[cfg(debug_assertions)]
+        test_shutdown,
+        validate_backend,
+        run_backend_once,
+        proxy_request,
+        ping,
+        get_api_token,
+        rotate_api_token,
+        reload_backend_config,
+        reload_backend_with_config,
+        write_backend_stdin,
+        start_backend,
+        stop_backend,
+        set_backend_log_level,
+        restart_backend,
+        list_sidecars,
+        restart_all_backends,
+        quit_app
+    ]
+}
@@ -0,0 +1,127 @@
+//! Restarting or reloading the backend when its config files change.
+//!
+//! Changes are picked up from OS file notifications through `notify`. A
+//! watched file is watched through its directory, so it is still seen after
+//! an editor saves by renaming a new copy over it.
+
+use crate::sidecar::{ShutdownReason, SidecarManager};
+use log::{error, info, warn};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+/// Default quiet period before acting on a change.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// What to do when a watched file changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchAction {
+    /// [`SidecarManager::restart`].
+    Restart,
+    /// [`SidecarManager::reload_config`].
+    Reload,
+}
+
+/// A file or directory to watch, see
+/// [`with_config_watch`](SidecarManager::with_config_watch).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigWatch {
+    pub path: PathBuf,
+    pub action: WatchAction,
+    /// Changes are coalesced until none have happened for this long, so an
+    /// editor's burst of writes on save triggers a single action.
+    pub debounce: Duration,
+}
+
+/// Watch the configured path for as long as the app is alive, running the
+/// configured action after each debounced burst of changes. Logs an error
+/// and watches nothing if the path (or a file's directory) doesn't exist.
+pub fn spawn_config_watcher(manager: Arc<Mutex<SidecarManager>>, watch: ConfigWatch) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let watcher = match watch_path(&watch.path, tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            error!("Failed to watch {}: {}", watch.path.display(), e);
+            return;
+        }
+    };
+
+    tauri::async_runtime::spawn(async move {
+        // Events stop once the watcher is dropped
+        let _watcher = watcher;
+        while let Some(changed) = rx.recv().await {
+            // Wait for the burst to settle
+            while let Ok(Some(_)) = tokio::time::timeout(watch.debounce, rx.recv()).await {}
+
+            if !manager.lock().await.status().status.is_running() {
+                info!("{} changed, API server is not running", changed.display());
+                continue;
+            }
+            match watch.action {
+                WatchAction::Restart => {
+                    info!("{} changed, restarting API server...", changed.display());
+                    let restarted =
+                        SidecarManager::restart_shared_for(&manager, ShutdownReason::ConfigChange)
+                            .await;
+                    if let Err(e) = restarted {
+                        error!("Failed to restart API server: {}", e);
+                    }
+                }
+                WatchAction::Reload => {
+                    info!(
+                        "{} changed, reloading API server config...",
+                        changed.display()
+                    );
+                    match manager.lock().await.reload_config().await {
+                        Ok(true) => {}
+                        Ok(false) => warn!("API server did not acknowledge the config reload"),
+                        Err(e) => error!("Failed to reload API server config: {}", e),
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Start watching `path`, sending every file below it that is added,
+/// modified or removed to `tx`, or just `path` itself if it is a file.
+fn watch_path(
+    path: &Path,
+    tx: mpsc::UnboundedSender<PathBuf>,
+) -> notify::Result<RecommendedWatcher> {
+    let (dir, file, mode) = if path.is_dir() {
+        (path, None, RecursiveMode::Recursive)
+    } else {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let file: Option<OsString> = path.file_name().map(Into::into);
+        (dir, file, RecursiveMode::NonRecursive)
+    };
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Config watch error: {}", e);
+                return;
+            }
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        let changed = event
+            .paths
+            .into_iter()
+            .find(|changed| file.is_none() || changed.file_name() == file.as_deref());
+        if let Some(changed) = changed {
+            let _ = tx.send(changed);
+        }
+    })?;
+    watcher.watch(dir, mode)?;
+    Ok(watcher)
+}
@@ -0,0 +1,68 @@
+//! Errors returned by the sidecar manager.
+
+use std::fmt;
+
+/// Error from a [`SidecarManager`](crate::sidecar::SidecarManager) operation.
+///
+/// Commands convert it to a `String` for IPC via `to_string()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SidecarError {
+    /// `start` was called while the sidecar was already running and the
+    /// manager is in strict mode.
+    AlreadyRunning,
+    /// The operation needs a running backend, but it is stopped or has
+    /// exited.
+    NotRunning,
+    /// The operation needs the app handle, but neither `initialize`,
+    /// `start` nor `set_app_handle` has provided one yet.
+    NoAppHandle,
+    /// Another instance of the app holds the sidecar's instance lock, see
+    /// [`SingleInstance`](crate::sidecar::SingleInstance).
+    AlreadyManaged,
+    /// The sidecar exited or never became ready while starting.
+    StartupFailed {
+        message: String,
+        /// The first stderr line matching the error pattern, as a one-line
+        /// headline for error dialogs.
+        failure_reason: Option<String>,
+        /// The last stderr lines captured since the process was spawned.
+        stderr_tail: Vec<String>,
+    },
+    /// Any other failure, already described for display.
+    Other(String),
+}
+
+impl fmt::Display for SidecarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyRunning => write!(f, "API server is already running"),
+            Self::NotRunning => write!(f, "API server is not running"),
+            Self::NoAppHandle => write!(f, "API server has no app handle, start it first"),
+            Self::AlreadyManaged => write!(
+                f,
+                "API server is already managed by another instance of the app"
+            ),
+            Self::StartupFailed {
+                message,
+                failure_reason: Some(reason),
+                ..
+            } => write!(f, "{}: {}", message, reason),
+            Self::StartupFailed { message, .. } => write!(f, "{}", message),
+            Self::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for SidecarError {}
+
+impl From<String> for SidecarError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for SidecarError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
@@ -0,0 +1,284 @@
+//! Minimal HTTP/1.1 client for talking to the local backend.
+//!
+//! The sidecar only ever serves plain HTTP on 127.0.0.1, so a small
+//! `Connection: close` client is enough and avoids pulling in a full
+//! HTTP stack.
+
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Largest response we are willing to buffer.
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A fully buffered HTTP response.
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// How long the backend took to answer, from the request being sent
+    /// to the last byte of the response.
+    pub waited: Duration,
+}
+
+impl Response {
+    /// Value of the first header called `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Headers for a request the app itself sends to the backend: `headers`,
+/// followed by `Authorization: <authorization>` if there is one.
+pub(crate) fn control_headers<'a>(
+    authorization: Option<&'a str>,
+    headers: &[(&'a str, &'a str)],
+) -> Vec<(&'a str, &'a str)> {
+    let authorization = authorization.map(|value| ("Authorization", value));
+    headers.iter().copied().chain(authorization).collect()
+}
+
+/// Send a request to `127.0.0.1:{port}{path}` and buffer the response.
+pub(crate) async fn request(
+    port: u16,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+    timeout: Duration,
+) -> Result<Response, String> {
+    request_limited(
+        port,
+        method,
+        path,
+        headers,
+        body,
+        timeout,
+        MAX_RESPONSE_BYTES,
+    )
+    .await
+}
+
+/// [`request`], failing once the response exceeds `max_bytes` instead of
+/// buffering it.
+pub(crate) async fn request_limited(
+    port: u16,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+    timeout: Duration,
+    max_bytes: u64,
+) -> Result<Response, String> {
+    let response = send(port, method, path, headers, body, max_bytes);
+    tokio::time::timeout(timeout, response)
+        .await
+        .map_err(|_| format!("{} {} timed out after {:?}", method, path, timeout))?
+}
+
+async fn send(
+    port: u16,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+    max_bytes: u64,
+) -> Result<Response, String> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to connect to port {}: {}", port, e))?;
+
+    let body = body.unwrap_or_default();
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        method,
+        path,
+        port,
+        body.len()
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    stream
+        .write_all(head.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    stream
+        .write_all(body)
+        .await
+        .map_err(|e| format!("Failed to send request body: {}", e))?;
+
+    let sent = Instant::now();
+    let mut raw = Vec::new();
+    (&mut stream)
+        .take(max_bytes + 1)
+        .read_to_end(&mut raw)
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    if raw.len() as u64 > max_bytes {
+        return Err(format!("Response is larger than {} bytes", max_bytes));
+    }
+    let waited = sent.elapsed();
+
+    let mut response = parse_response(&raw)?;
+    response.waited = waited;
+    Ok(response)
+}
+
+fn parse_response(raw: &[u8]) -> Result<Response, String> {
+    let head_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("Malformed HTTP response: missing header terminator")?;
+    let head = String::from_utf8_lossy(&raw[..head_end]);
+    let mut lines = head.split("\r\n");
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or("Malformed HTTP response: bad status line")?;
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let mut response = Response {
+        status,
+        headers,
+        body: Vec::new(),
+        waited: Duration::ZERO,
+    };
+
+    let rest = &raw[head_end + 4..];
+    response.body = if response
+        .header("transfer-encoding")
+        .is_some_and(|te| te.eq_ignore_ascii_case("chunked"))
+    {
+        decode_chunked(rest)?
+    } else if let Some(len) = response
+        .header("content-length")
+        .and_then(|len| len.parse::<usize>().ok())
+    {
+        rest[..len.min(rest.len())].to_vec()
+    } else {
+        rest.to_vec()
+    };
+
+    Ok(response)
+}
+
+fn decode_chunked(mut data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or("Malformed chunked body")?;
+        let size_field = String::from_utf8_lossy(&data[..line_end]);
+        let size_hex = size_field.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| format!("Malformed chunk size: {:?}", size_hex))?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        if data.len() < size {
+            return Err("Truncated chunked body".into());
+        }
+        body.extend_from_slice(&data[..size]);
+        data = data.get(size + 2..).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::{Ipv4Addr, TcpListener};
+
+    #[test]
+    fn parse_response_reads_status_headers_and_a_sized_body() {
+        let response = parse_response(
+            b"HTTP/1.1 201 Created\r\nContent-Length: 5\r\nX-Id: 7\r\n\r\nhello, and more",
+        )
+        .unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.header("x-id"), Some("7"));
+        assert_eq!(response.body, b"hello");
+    }
+
+    #[test]
+    fn parse_response_decodes_a_chunked_body() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\n\r\n";
+        assert_eq!(parse_response(raw).unwrap().body, b"hello, world");
+    }
+
+    #[test]
+    fn parse_response_rejects_a_truncated_response() {
+        let error = parse_response(b"HTTP/1.1 200 OK\r\nContent-Le").unwrap_err();
+        assert!(error.contains("missing header terminator"), "{}", error);
+        let error = parse_response(b"HTTP/1.1\r\n\r\n").unwrap_err();
+        assert!(error.contains("bad status line"), "{}", error);
+
+        let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\na\r\nhello";
+        assert_eq!(
+            parse_response(chunked).unwrap_err(),
+            "Truncated chunked body"
+        );
+        assert_eq!(
+            decode_chunked(b"5\r\nhello\r\n").unwrap_err(),
+            "Malformed chunked body"
+        );
+        assert_eq!(
+            decode_chunked(b"zz\r\n").unwrap_err(),
+            "Malformed chunk size: \"zz\""
+        );
+    }
+
+    /// A server on a free port that sends `response` to the first client.
+    fn serve_once(response: Vec<u8>) -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let _ = stream.write_all(&response);
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn request_limited_rejects_a_response_over_the_limit() {
+        let body = vec![b'x'; 1000];
+        let mut response = b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\n\r\n".to_vec();
+        response.extend_from_slice(&body);
+        let len = response.len() as u64;
+        let limited = |max_bytes| {
+            let port = serve_once(response.clone());
+            tauri::async_runtime::block_on(request_limited(
+                port,
+                "GET",
+                "/",
+                &[],
+                None,
+                Duration::from_secs(5),
+                max_bytes,
+            ))
+        };
+
+        assert_eq!(limited(len).unwrap().body, body);
+        assert_eq!(
+            limited(len - 1).unwrap_err(),
+            format!("Response is larger than {} bytes", len - 1)
+        );
+    }
+}
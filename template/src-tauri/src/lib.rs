@@ -0,0 +1,22 @@
+//! Sidecar management for the Tether desktop shell.
+
+pub mod app;
+pub mod cgroup;
+pub mod commands;
+pub mod config_watch;
+pub mod error;
+mod http;
+pub mod logging;
+pub mod logs;
+pub mod metrics;
+pub mod monitor;
+pub mod plugin;
+pub mod presets;
+mod process;
+pub mod proxy;
+pub mod registry;
+pub mod resolve;
+pub mod sidecar;
+pub mod signals;
+mod stats;
+pub mod storage;
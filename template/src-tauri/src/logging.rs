@@ -0,0 +1,72 @@
+//! Logger for the manager's own diagnostics, filtered with `RUST_LOG`.
+//!
+//! `RUST_LOG` takes comma-separated directives, each either a level or
+//! `target=level`, e.g. `warn,tether_app=debug`. The sidecar's own output is
+//! forwarded separately and isn't affected.
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Used when `RUST_LOG` is unset: our lifecycle messages, and only warnings
+/// from dependencies.
+const DEFAULT_FILTER: &str = "warn,tether_app=info";
+
+struct Logger {
+    /// `(target prefix, level)`; a `None` prefix applies to everything.
+    directives: Vec<(Option<String>, LevelFilter)>,
+}
+
+impl Logger {
+    fn parse(spec: &str) -> Self {
+        let directives = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .filter_map(|directive| match directive.split_once('=') {
+                Some((target, level)) => Some((Some(target.to_string()), level.parse().ok()?)),
+                None => Some((None, directive.parse().ok()?)),
+            })
+            .collect();
+        Self { directives }
+    }
+
+    /// Level of the most specific directive matching `target`.
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| prefix.as_deref().is_none_or(|p| target.starts_with(p)))
+            .max_by_key(|(prefix, _)| prefix.as_ref().map_or(0, String::len))
+            .map_or(LevelFilter::Error, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .max()
+            .unwrap_or(LevelFilter::Error)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the logger. Call once, before anything logs.
+pub fn init() {
+    let spec = std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+    let logger = Logger::parse(&spec);
+    let max_level = logger.max_level();
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
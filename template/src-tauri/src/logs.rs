@@ -0,0 +1,425 @@
+//! In-memory ring buffer of recent sidecar output, and paging through the
+//! backend's log file on disk.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most lines [`read_page`] returns at once.
+pub const MAX_PAGE_LINES: usize = 1000;
+
+/// Lines longer than this are split, so one runaway line can't exhaust
+/// memory.
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+/// Default number of lines kept in the ring buffer.
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+/// Default limit on the combined size of the text held in the buffer.
+pub const DEFAULT_LOG_BYTE_BUDGET: usize = 2 * 1024 * 1024;
+
+/// Which output stream a line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    /// Index for per-stream counters.
+    fn slot(self) -> usize {
+        match self {
+            LogStream::Stdout => 0,
+            LogStream::Stderr => 1,
+        }
+    }
+}
+
+/// Severity of a line of sidecar output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// One line of sidecar output. Also the payload of the `sidecar-log` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// [Name](crate::sidecar::SidecarManager::with_name) of the sidecar
+    /// that wrote the line.
+    pub sidecar: String,
+    pub stream: LogStream,
+    pub level: LogLevel,
+    /// Milliseconds since the Unix epoch.
+    pub ts: u64,
+    pub text: String,
+}
+
+impl LogEntry {
+    /// Capture `text` from `sidecar`'s `stream` at `level`, timestamped now.
+    pub fn new(sidecar: &str, stream: LogStream, level: LogLevel, text: String) -> Self {
+        Self {
+            sidecar: sidecar.to_string(),
+            stream,
+            level,
+            ts: now_ms(),
+            text,
+        }
+    }
+}
+
+/// Current time in milliseconds since the Unix epoch, as used in
+/// [`LogEntry::ts`].
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// One page of a log file, returned by the `read_log_file` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogPage {
+    pub lines: Vec<String>,
+    /// Byte offset to pass for the next page, or `None` at the end of the
+    /// file.
+    pub next_offset: Option<u64>,
+}
+
+/// Read up to `limit` lines (capped at [`MAX_PAGE_LINES`]) of `path`,
+/// starting at byte `offset`. Only the page itself is held in memory.
+pub fn read_page(path: &Path, offset: u64, limit: usize) -> io::Result<LogPage> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(offset.min(len)))?;
+    let mut reader = BufReader::new(file);
+    let mut position = offset.min(len);
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    while lines.len() < limit.min(MAX_PAGE_LINES) {
+        buf.clear();
+        let read = (&mut reader)
+            .take(MAX_LINE_BYTES)
+            .read_until(b'\n', &mut buf)?;
+        if read == 0 {
+            break;
+        }
+        position += read as u64;
+        let line = String::from_utf8_lossy(&buf);
+        lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+    Ok(LogPage {
+        lines,
+        next_offset: (position < len).then_some(position),
+    })
+}
+
+/// The `n`th rotated copy of the log at `path`, e.g. `backend.log.1`, or
+/// `path` itself for 0.
+pub fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    if n == 0 {
+        return path.to_path_buf();
+    }
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", n));
+    PathBuf::from(rotated)
+}
+
+/// Rotate the log at `path` now: shift existing copies up by one, copy it
+/// to `.1` and truncate it. Returns the path of the copy.
+///
+/// Copying and truncating instead of renaming keeps a backend that has the
+/// file open (in append mode) writing to the fresh file, and works on
+/// Windows, where open files can't be renamed.
+pub fn rotate(path: &Path) -> io::Result<PathBuf> {
+    let mut last = 0;
+    while rotated_path(path, last + 1).exists() {
+        last += 1;
+    }
+    for n in (1..=last).rev() {
+        std::fs::rename(rotated_path(path, n), rotated_path(path, n + 1))?;
+    }
+    let rotated = rotated_path(path, 1);
+    std::fs::copy(path, &rotated)?;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)?
+        .set_len(0)?;
+    Ok(rotated)
+}
+
+/// Result of `get_recent_logs`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum RecentLogs {
+    Structured(Vec<LogEntry>),
+    /// Just the text of each line, for callers that predate structured
+    /// entries.
+    Flat(Vec<String>),
+}
+
+/// Decides which output lines are stored and forwarded: a line must match
+/// one of the include patterns, if there are any, and none of the exclude
+/// patterns.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LogFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl LogFilter {
+    pub fn include(&mut self, pattern: Regex) {
+        self.include.push(pattern);
+    }
+
+    pub fn exclude(&mut self, pattern: Regex) {
+        self.exclude.push(pattern);
+    }
+
+    pub fn allows(&self, line: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.is_match(line)))
+            && !self.exclude.iter().any(|p| p.is_match(line))
+    }
+}
+
+/// Decides the level of each output line. Stdout is always info; a stderr
+/// line gets the level of the first rule it matches, or the default.
+#[derive(Debug, Clone)]
+pub(crate) struct LogLevels {
+    stderr_rules: Vec<(Regex, LogLevel)>,
+    stderr_default: LogLevel,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            stderr_rules: Vec::new(),
+            stderr_default: LogLevel::Error,
+        }
+    }
+}
+
+impl LogLevels {
+    pub fn stderr_rule(&mut self, pattern: Regex, level: LogLevel) {
+        self.stderr_rules.push((pattern, level));
+    }
+
+    pub fn set_stderr_default(&mut self, level: LogLevel) {
+        self.stderr_default = level;
+    }
+
+    pub fn classify(&self, stream: LogStream, line: &str) -> LogLevel {
+        match stream {
+            LogStream::Stdout => LogLevel::Info,
+            LogStream::Stderr => self
+                .stderr_rules
+                .iter()
+                .find(|(pattern, _)| pattern.is_match(line))
+                .map_or(self.stderr_default, |(_, level)| *level),
+        }
+    }
+}
+
+/// How a log search matches lines.
+///
+/// Only substring matching for now; further kinds (e.g. regex) go here.
+enum Matcher {
+    Substring {
+        needle: String,
+        case_insensitive: bool,
+    },
+}
+
+impl Matcher {
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Matcher::Substring {
+                needle,
+                case_insensitive: false,
+            } => text.contains(needle.as_str()),
+            Matcher::Substring {
+                needle,
+                case_insensitive: true,
+            } => text.to_lowercase().contains(needle.as_str()),
+        }
+    }
+}
+
+/// Bounded buffer of the most recent [`LogEntry`]s, limited by line count,
+/// optionally by line count per stream, and by the total bytes of text it
+/// holds.
+pub(crate) struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    /// Line limits for stdout and stderr, indexed by [`LogStream::slot`].
+    stream_capacity: [usize; 2],
+    stream_lines: [usize; 2],
+    byte_budget: usize,
+    bytes: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize, byte_budget: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity.min(DEFAULT_LOG_CAPACITY)),
+            capacity,
+            stream_capacity: [usize::MAX; 2],
+            stream_lines: [0; 2],
+            byte_budget,
+            bytes: 0,
+        }
+    }
+
+    /// Append an entry, evicting the oldest until the line counts and the
+    /// byte budget are satisfied. A line bigger than the whole budget is
+    /// truncated to fit.
+    pub fn push(&mut self, mut entry: LogEntry) {
+        let slot = entry.stream.slot();
+        if self.capacity == 0 || self.stream_capacity[slot] == 0 || self.byte_budget == 0 {
+            return;
+        }
+        if entry.text.len() > self.byte_budget {
+            let mut end = self.byte_budget;
+            while !entry.text.is_char_boundary(end) {
+                end -= 1;
+            }
+            entry.text.truncate(end);
+        }
+        while self.stream_lines[slot] >= self.stream_capacity[slot] {
+            if !self.evict_oldest(Some(entry.stream)) {
+                break;
+            }
+        }
+        while self.entries.len() >= self.capacity
+            || self.bytes + entry.text.len() > self.byte_budget
+        {
+            if !self.evict_oldest(None) {
+                break;
+            }
+        }
+        self.bytes += entry.text.len();
+        self.stream_lines[slot] += 1;
+        self.entries.push_back(entry);
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.shrink();
+    }
+
+    pub fn set_stream_capacity(&mut self, stream: LogStream, capacity: usize) {
+        self.stream_capacity[stream.slot()] = capacity;
+        self.shrink();
+    }
+
+    pub fn set_byte_budget(&mut self, byte_budget: usize) {
+        self.byte_budget = byte_budget;
+        self.shrink();
+    }
+
+    /// Evict the oldest entries until all limits hold again.
+    fn shrink(&mut self) {
+        for stream in [LogStream::Stdout, LogStream::Stderr] {
+            let slot = stream.slot();
+            while self.stream_lines[slot] > self.stream_capacity[slot] {
+                if !self.evict_oldest(Some(stream)) {
+                    break;
+                }
+            }
+        }
+        while self.entries.len() > self.capacity || self.bytes > self.byte_budget {
+            if !self.evict_oldest(None) {
+                break;
+            }
+        }
+    }
+
+    /// Drop the oldest entry, from `stream` only if given. Returns whether
+    /// there was one.
+    fn evict_oldest(&mut self, stream: Option<LogStream>) -> bool {
+        let index = match stream {
+            Some(stream) => self.entries.iter().position(|entry| entry.stream == stream),
+            None => (!self.entries.is_empty()).then_some(0),
+        };
+        let Some(old) = index.and_then(|index| self.entries.remove(index)) else {
+            return false;
+        };
+        self.bytes = self.bytes.saturating_sub(old.text.len());
+        self.stream_lines[old.stream.slot()] -= 1;
+        true
+    }
+
+    /// The last `max_results` entries containing `query`, oldest first.
+    pub fn search(&self, query: &str, case_insensitive: bool, max_results: usize) -> Vec<LogEntry> {
+        let matcher = Matcher::Substring {
+            needle: if case_insensitive {
+                query.to_lowercase()
+            } else {
+                query.to_string()
+            },
+            case_insensitive,
+        };
+        let mut found: Vec<LogEntry> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| matcher.matches(&entry.text))
+            .take(max_results)
+            .cloned()
+            .collect();
+        found.reverse();
+        found
+    }
+
+    /// Stderr lines captured at or after `since_ms`, oldest first.
+    pub fn stderr_since(&self, since_ms: u64) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.stream == LogStream::Stderr && entry.ts >= since_ms)
+            .map(|entry| entry.text.clone())
+            .collect()
+    }
+
+    /// Stderr lines at [`LogLevel::Error`] captured at or after `since_ms`,
+    /// oldest first.
+    pub fn errors_since(&self, since_ms: u64) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| {
+                entry.stream == LogStream::Stderr
+                    && entry.level == LogLevel::Error
+                    && entry.ts >= since_ms
+            })
+            .map(|entry| entry.text.clone())
+            .collect()
+    }
+
+    /// Entries captured at or after `since_ms`, oldest first.
+    pub fn since(&self, since_ms: u64) -> Vec<LogEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.ts >= since_ms)
+            .cloned()
+            .collect()
+    }
+
+    /// The last `limit` entries, from `stream` only if given, oldest first.
+    pub fn recent(&self, limit: usize, stream: Option<LogStream>) -> Vec<LogEntry> {
+        let mut recent: Vec<LogEntry> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| stream.is_none_or(|stream| entry.stream == stream))
+            .take(limit)
+            .cloned()
+            .collect();
+        recent.reverse();
+        recent
+    }
+}
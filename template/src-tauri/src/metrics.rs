@@ -0,0 +1,171 @@
+//! Manager metrics in the Prometheus text format, served on localhost for
+//! scraping, see [`SidecarManager::with_metrics_port`].
+
+use crate::sidecar::{BackendMetrics, SidecarManager, SidecarStatus};
+use log::{debug, error, info};
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+/// How long a scraper has to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Every status, for the one-hot `tether_backend_status` gauge.
+const STATUSES: [SidecarStatus; 6] = [
+    SidecarStatus::Stopped,
+    SidecarStatus::Running,
+    SidecarStatus::RunningFallback,
+    SidecarStatus::Restarting,
+    SidecarStatus::Compromised,
+    SidecarStatus::Maintenance,
+];
+
+/// Serve `GET /metrics` on `127.0.0.1:port` for as long as the app is
+/// alive.
+pub fn spawn_metrics_server(manager: Arc<Mutex<SidecarManager>>, port: u16) {
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to serve metrics on port {}: {}", port, e);
+                return;
+            }
+        };
+        info!("Serving metrics on http://127.0.0.1:{}/metrics", port);
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    debug!("Failed to accept metrics connection: {}", e);
+                    continue;
+                }
+            };
+            let manager = manager.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = respond(stream, &manager).await {
+                    debug!("Failed to serve metrics: {}", e);
+                }
+            });
+        }
+    });
+}
+
+/// Answer one scrape. Only the request line is looked at.
+async fn respond(mut stream: TcpStream, manager: &Mutex<SidecarManager>) -> std::io::Result<()> {
+    let mut buf = [0; 1024];
+    let read = tokio::time::timeout(REQUEST_TIMEOUT, stream.read(&mut buf))
+        .await
+        .map_err(|_| std::io::ErrorKind::TimedOut)??;
+    let request = String::from_utf8_lossy(&buf[..read]);
+    let (status, body) = if request.starts_with("GET /metrics ") {
+        let metrics = manager.lock().await.metrics().await;
+        ("200 OK", render(&metrics))
+    } else {
+        ("404 Not Found", String::new())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// `metrics` in the Prometheus text exposition format.
+fn render(metrics: &BackendMetrics) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: Option<f64>| {
+        let Some(value) = value else {
+            return;
+        };
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    };
+
+    metric(
+        "tether_backend_up",
+        "gauge",
+        "Whether the backend is running.",
+        Some(f64::from(u8::from(metrics.status.is_running()))),
+    );
+    metric(
+        "tether_backend_uptime_seconds",
+        "gauge",
+        "Time the current backend process has been running.",
+        Some(metrics.uptime_secs as f64),
+    );
+    metric(
+        "tether_backend_restarts_total",
+        "counter",
+        "Restarts of the backend, requested or after a crash.",
+        Some(f64::from(metrics.restart_count)),
+    );
+    metric(
+        "tether_backend_crashes_total",
+        "counter",
+        "Backend processes that exited without being stopped.",
+        Some(f64::from(metrics.crash_count)),
+    );
+    metric(
+        "tether_backend_crash_restarts_total",
+        "counter",
+        "Restarts after a crash.",
+        Some(metrics.crash_restarts as f64),
+    );
+    metric(
+        "tether_backend_successful_startups_total",
+        "counter",
+        "Backend processes that became ready.",
+        Some(metrics.successful_startups as f64),
+    );
+    metric(
+        "tether_backend_probe_attempts_total",
+        "counter",
+        "Readiness and health probes sent.",
+        Some(metrics.probe_attempts as f64),
+    );
+    metric(
+        "tether_backend_probe_failures_total",
+        "counter",
+        "Readiness and health probes that failed.",
+        Some(metrics.probe_failures as f64),
+    );
+    metric(
+        "tether_backend_probe_latency_seconds",
+        "gauge",
+        "Latency of the latest health probe.",
+        metrics.last_probe_latency_ms.map(|ms| ms as f64 / 1000.0),
+    );
+    metric(
+        "tether_backend_rss_bytes",
+        "gauge",
+        "Resident memory of the backend process.",
+        metrics.rss_bytes.map(|bytes| bytes as f64),
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP tether_backend_status Current status of the backend."
+    );
+    let _ = writeln!(out, "# TYPE tether_backend_status gauge");
+    for status in STATUSES {
+        let name = serde_json::to_value(status)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "tether_backend_status{{status=\"{}\"}} {}",
+            name,
+            u8::from(metrics.status == status)
+        );
+    }
+    out
+}
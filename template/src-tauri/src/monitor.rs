@@ -0,0 +1,121 @@
+//! Periodic health monitor for the sidecar.
+
+use crate::sidecar::{AutoRestart, RestartAttempt, SidecarManager};
+use log::{error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// How long a crash-restarted instance has to start accepting connections
+/// before the attempt counts as failed.
+const RECOVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run [`SidecarManager::monitor_tick`] every `monitor_interval` for as
+/// long as the app is alive, and restart the sidecar when it crashes if
+/// [`with_auto_restart`](SidecarManager::with_auto_restart) is set.
+pub fn spawn_health_monitor(manager: Arc<Mutex<SidecarManager>>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let (interval, crashed) = {
+                let manager = manager.lock().await;
+                (manager.monitor_interval(), manager.crash_notifier())
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => manager.lock().await.monitor_tick().await,
+                _ = crashed.notified() => recover(&manager).await,
+            }
+        }
+    });
+}
+
+/// Restart a crashed sidecar with exponential backoff, emitting
+/// `backend-restart-attempt` before each attempt.
+///
+/// The manager is only locked around each step, so commands keep working
+/// while we wait.
+async fn recover(manager: &Mutex<SidecarManager>) {
+    let Some(policy) = manager.lock().await.auto_restart() else {
+        return;
+    };
+    manager.lock().await.begin_restart();
+    recover_with(manager, policy).await;
+    manager.lock().await.end_restart();
+}
+
+/// Whether crash recovery should go on: the sidecar is still down and auto
+/// restart hasn't been turned off.
+fn keep_recovering(manager: &SidecarManager) -> bool {
+    if manager.auto_restart().is_none() {
+        info!("Auto restart turned off, leaving the API server stopped");
+        return false;
+    }
+    manager.crashed()
+}
+
+async fn recover_with(manager: &Mutex<SidecarManager>, policy: AutoRestart) {
+    let (cancelled, used) = {
+        let manager = manager.lock().await;
+        (
+            manager.restart_cancel_notifier(),
+            manager.restart_attempts_used(),
+        )
+    };
+    // Carry on from earlier crashes that happened before the backend was
+    // stable again
+    for attempt in used + 1..=policy.max_attempts {
+        let delay = policy.delay(attempt - 1);
+        {
+            let mut manager = manager.lock().await;
+            if !keep_recovering(&manager) {
+                return;
+            }
+            manager.set_restart_attempts_used(attempt);
+            manager.emit_restart_attempt(RestartAttempt {
+                attempt,
+                max: policy.max_attempts,
+                delay_ms: delay.as_millis() as u64,
+            });
+        }
+        info!(
+            "Restarting crashed API server in {:?} (attempt {} of {})...",
+            delay, attempt, policy.max_attempts
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = cancelled.notified() => {}
+        }
+
+        let ready = {
+            let mut manager = manager.lock().await;
+            if !keep_recovering(&manager) {
+                return;
+            }
+            match manager.restart_after_crash().await {
+                Ok(ready) => ready,
+                Err(e) => {
+                    error!("Failed to restart API server: {}", e);
+                    continue;
+                }
+            }
+        };
+        let since_ms = ready.since_ms;
+        match ready.wait(RECOVERY_TIMEOUT).await {
+            Ok(_) => {
+                info!("API server recovered");
+                return;
+            }
+            Err(e) => {
+                let failure = manager.lock().await.startup_failure(
+                    format!("Restarted API server did not come up ({})", e),
+                    since_ms,
+                );
+                warn!("{}", failure);
+            }
+        }
+    }
+
+    warn!(
+        "Giving up on restarting the API server after {} attempts",
+        policy.max_attempts
+    );
+}
@@ -0,0 +1,168 @@
+//! The sidecar packaged as a Tauri plugin.
+//!
+//! An alternative to [`app::builder`] for apps that already have a
+//! `tauri::Builder` of their own:
+//!
+//! ```ignore
+//! tauri::Builder::default()
+//!     .plugin(tauri_plugin_shell::init())
+//!     .plugin(tether_app::plugin::Builder::new().binary("api").build())
+//! ```
+//!
+//! The plugin manages the state, registers the [`commands`], starts the
+//! sidecars and shuts them down when the app exits. Being a plugin, its
+//! commands are invoked as `plugin:tether|get_api_port` and need a
+//! permission: declare them in `build.rs` with
+//! `tauri_build::Attributes::new().plugin("tether", InlinedPlugin::new().commands(..).default_permission(DefaultPermissionRule::AllowAllCommands))`
+//! and add `tether:default` to the capability.
+
+use crate::app;
+use crate::commands;
+use crate::error::SidecarError;
+use crate::presets::SidecarPreset;
+use crate::registry::SidecarRegistry;
+use crate::sidecar::SidecarManager;
+use log::error;
+use std::time::Duration;
+use tauri::plugin::TauriPlugin;
+use tauri::{Manager, RunEvent, WindowEvent, Wry};
+
+/// Name the plugin is registered under.
+pub const PLUGIN_NAME: &str = "tether";
+
+type Configure = Box<dyn FnOnce(SidecarManager) -> SidecarManager + Send>;
+
+type ConfigureRegistry =
+    Box<dyn FnOnce(SidecarRegistry) -> Result<SidecarRegistry, SidecarError> + Send>;
+
+/// Builds the tether plugin. The setters apply the [`SidecarManager`]
+/// builder method of the same name, in the order they are called, to the
+/// manager from [`app::default_manager`]; [`configure`](Self::configure)
+/// reaches the rest, and [`registry`](Self::registry) the
+/// [`SidecarRegistry`].
+pub struct Builder {
+    configure: Vec<Configure>,
+    configure_registry: Vec<ConfigureRegistry>,
+    autostart: bool,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            configure: Vec::new(),
+            configure_registry: Vec::new(),
+            autostart: true,
+        }
+    }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply any [`SidecarManager`] builder calls.
+    pub fn configure(
+        mut self,
+        configure: impl FnOnce(SidecarManager) -> SidecarManager + Send + 'static,
+    ) -> Self {
+        self.configure.push(Box::new(configure));
+        self
+    }
+
+    /// Apply any [`SidecarRegistry`] builder calls, e.g. to keep the
+    /// sidecars alive on close or add more of them, see
+    /// [`app::manage_with`].
+    pub fn registry(
+        mut self,
+        configure: impl FnOnce(SidecarRegistry) -> Result<SidecarRegistry, SidecarError>
+            + Send
+            + 'static,
+    ) -> Self {
+        self.configure_registry.push(Box::new(configure));
+        self
+    }
+
+    pub fn binary(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.configure(move |manager| manager.with_binary(name))
+    }
+
+    pub fn preset(self, preset: SidecarPreset) -> Self {
+        self.configure(move |manager| manager.with_preset(preset))
+    }
+
+    pub fn env(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        self.configure(move |manager| manager.with_env(key, value))
+    }
+
+    pub fn health_path(self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        self.configure(move |manager| manager.with_health_path(path))
+    }
+
+    pub fn startup_timeout(self, timeout: Duration) -> Self {
+        self.configure(move |manager| manager.with_startup_timeout(timeout))
+    }
+
+    pub fn auto_restart(self, max_attempts: u32, initial_delay: Duration) -> Self {
+        self.configure(move |manager| manager.with_auto_restart(max_attempts, initial_delay))
+    }
+
+    /// Start the sidecars as soon as the plugin is set up (default `true`,
+    /// unless the manager is built with
+    /// [`with_manual_start`](SidecarManager::with_manual_start)). Without
+    /// it, the frontend starts it with `start_backend`.
+    pub fn autostart(mut self, autostart: bool) -> Self {
+        self.autostart = autostart;
+        self
+    }
+
+    pub fn build(self) -> TauriPlugin<Wry> {
+        let Self {
+            configure,
+            configure_registry,
+            autostart,
+        } = self;
+        tauri::plugin::Builder::new(PLUGIN_NAME)
+            .invoke_handler(commands::handler())
+            .setup(move |app, _api| {
+                let manager = app::default_manager(|manager| {
+                    configure.into_iter().fold(manager, |manager, f| f(manager))
+                })?;
+                let autostart = autostart && !manager.manual_start();
+                app::manage_with(app, manager, |registry| {
+                    configure_registry
+                        .into_iter()
+                        .try_fold(registry, |registry, f| f(registry))
+                })?;
+                if autostart {
+                    app::start_all_in_background(app);
+                }
+                Ok(())
+            })
+            .on_event(|app, event| match event {
+                RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::CloseRequested { api, .. },
+                    ..
+                } => {
+                    app::keep_alive_on_close(app, api, || match app.get_webview_window(label) {
+                        Some(window) => window.hide(),
+                        None => Ok(()),
+                    });
+                }
+                RunEvent::Exit => {
+                    let registry = app.state::<SidecarRegistry>();
+                    tauri::async_runtime::block_on(async {
+                        if let Err(e) = registry.shutdown().await {
+                            error!("{}", e);
+                        }
+                    });
+                }
+                _ => {}
+            })
+            .build()
+    }
+}
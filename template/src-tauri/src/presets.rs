@@ -0,0 +1,56 @@
+//! Defaults for known kinds of backend.
+
+/// Environment set for every sidecar unless overridden with
+/// [`with_env_defaults`](crate::sidecar::SidecarManager::with_env_defaults).
+///
+/// These are for Python sidecars: without `PYTHONUNBUFFERED`, Python
+/// block-buffers stdout when it isn't a terminal, so log lines only show up
+/// in bursts or when the process exits. Other runtimes ignore it.
+pub const PYTHON_ENV_DEFAULTS: &[(&str, &str)] = &[("PYTHONUNBUFFERED", "1")];
+
+/// Defaults for a common backend stack, applied with
+/// [`with_preset`](crate::sidecar::SidecarManager::with_preset).
+///
+/// Builder calls made after the preset override its values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidecarPreset {
+    /// FastAPI served by uvicorn, as in the template backend.
+    FastApi,
+    /// Flask's development server.
+    Flask,
+    /// A plain socket server: ready once the port accepts connections, and
+    /// no Python environment defaults.
+    RawTcp,
+    /// Leave every setting as it is.
+    Custom,
+}
+
+impl SidecarPreset {
+    /// Endpoint to probe for health, if the preset has an opinion.
+    pub fn health_path(self) -> Option<&'static str> {
+        match self {
+            SidecarPreset::FastApi | SidecarPreset::Flask => Some("/health"),
+            SidecarPreset::RawTcp | SidecarPreset::Custom => None,
+        }
+    }
+
+    /// Environment defaults, or `None` to keep the current ones.
+    pub fn env_defaults(self) -> Option<&'static [(&'static str, &'static str)]> {
+        match self {
+            SidecarPreset::FastApi | SidecarPreset::Flask => Some(PYTHON_ENV_DEFAULTS),
+            SidecarPreset::RawTcp => Some(&[]),
+            SidecarPreset::Custom => None,
+        }
+    }
+
+    /// Output line that signals the server is ready. `Some(None)` means
+    /// readiness is detected from the port instead.
+    pub fn ready_pattern(self) -> Option<Option<&'static str>> {
+        match self {
+            SidecarPreset::FastApi => Some(Some("Application startup complete")),
+            SidecarPreset::Flask => Some(Some("Running on ")),
+            SidecarPreset::RawTcp => Some(None),
+            SidecarPreset::Custom => None,
+        }
+    }
+}
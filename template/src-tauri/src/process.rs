@@ -0,0 +1,363 @@
+//! Process signalling and cleanup.
+//!
+//! The manager shells out to `kill`/`pkill` or `taskkill`, which also covers
+//! process trees on Windows. Minimal containers may not ship those tools, so
+//! each operation falls back to the OS API when the tool is missing instead
+//! of failing silently and leaking the backend.
+
+use log::warn;
+use std::io::{self, ErrorKind};
+use std::process::{Command as StdCommand, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+static WARNED_MISSING_TOOL: AtomicBool = AtomicBool::new(false);
+
+/// Run `program`, returning whether it exited successfully, or `None` if it
+/// isn't installed.
+fn run(program: &str, args: &[&str]) -> Option<bool> {
+    match StdCommand::new(program).args(args).output() {
+        Ok(output) => Some(output.status.success()),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            if !WARNED_MISSING_TOOL.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "`{}` not found, falling back to direct process termination",
+                    program
+                );
+            }
+            None
+        }
+        Err(_) => Some(false),
+    }
+}
+
+/// Run `command` to completion and collect its output like
+/// [`StdCommand::output`], unless it takes longer than `timeout`: then it
+/// and the processes it spawned are killed, it is reaped, and `None`
+/// returned, so a timed-out helper never keeps running behind our back.
+pub(crate) async fn output_within(
+    command: StdCommand,
+    timeout: Duration,
+) -> io::Result<Option<Output>> {
+    let mut command = tokio::process::Command::from(command);
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command.spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let run = async {
+        let (status, stdout, stderr) =
+            tokio::try_join!(child.wait(), read_all(stdout), read_all(stderr))?;
+        Ok::<_, io::Error>(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    };
+    let finished = tokio::time::timeout(timeout, run).await;
+    match finished {
+        Ok(output) => output.map(Some),
+        Err(_) => {
+            if let Some(pid) = child.id() {
+                kill_children(pid);
+            }
+            child.kill().await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Everything left to read from `pipe`, if there is one.
+async fn read_all(pipe: Option<impl AsyncRead + Unpin>) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut buf).await?;
+    }
+    Ok(buf)
+}
+
+/// Run `spawn` with the process umask set to `mask`, so a child forked in
+/// it inherits the mask. Without a mask, or on Windows, it just runs
+/// `spawn`.
+///
+/// The umask is process-wide: files other threads create meanwhile get the
+/// mask too, which can only make them more restrictive.
+pub(crate) fn with_umask<T>(mask: Option<u32>, spawn: impl FnOnce() -> T) -> T {
+    #[cfg(unix)]
+    if let Some(mask) = mask {
+        // SAFETY: umask(2) has no memory-safety preconditions.
+        let previous = unsafe { libc::umask(mask as libc::mode_t) };
+        let result = spawn();
+        // SAFETY: as above.
+        unsafe { libc::umask(previous) };
+        return result;
+    }
+
+    #[cfg(windows)]
+    let _ = mask;
+    spawn()
+}
+
+/// Send SIGHUP to `pid`. Returns whether it was delivered; always `false`
+/// on Windows, which has no equivalent.
+pub(crate) fn hangup(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        run("kill", &["-HUP", &pid.to_string()]).unwrap_or_else(|| signal(pid, libc::SIGHUP))
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = pid;
+        false
+    }
+}
+
+/// Send SIGINT to `pid`. Returns whether it was delivered; always `false`
+/// on Windows, where console events can't be sent to a single process.
+pub(crate) fn interrupt(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        run("kill", &["-INT", &pid.to_string()]).unwrap_or_else(|| signal(pid, libc::SIGINT))
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = pid;
+        false
+    }
+}
+
+/// Send signal number `signal` to `pid`. Returns whether it was delivered;
+/// always `false` on Windows, which has no signals.
+pub(crate) fn send_signal(pid: u32, signal: i32) -> bool {
+    #[cfg(unix)]
+    {
+        self::signal(pid, signal)
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = (pid, signal);
+        false
+    }
+}
+
+/// Ask `pid` to exit gracefully. Windows has no graceful fallback, so
+/// without `taskkill` this does nothing and the caller's kill step follows.
+pub(crate) fn terminate(pid: u32) {
+    #[cfg(unix)]
+    {
+        if run("kill", &["-TERM", &pid.to_string()]).is_none() {
+            signal(pid, libc::SIGTERM);
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = run("taskkill", &["/T", "/PID", &pid.to_string()]);
+    }
+}
+
+/// Kill the processes spawned by `pid` (on Windows, `pid` included).
+pub(crate) fn kill_children(pid: u32) {
+    #[cfg(unix)]
+    {
+        if run("pkill", &["-P", &pid.to_string()]).is_none() {
+            for child in children(pid) {
+                signal(child, libc::SIGTERM);
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        if run("taskkill", &["/F", "/T", "/PID", &pid.to_string()]).is_none() {
+            win::kill_tree(pid);
+        }
+    }
+}
+
+/// Kill `pid` itself without waiting for it to exit.
+pub(crate) fn kill(pid: u32) {
+    #[cfg(unix)]
+    {
+        if run("kill", &["-KILL", &pid.to_string()]).is_none() {
+            signal(pid, libc::SIGKILL);
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        win::terminate(pid);
+    }
+}
+
+/// Whether `pid` is still running. Pids get reused, so this is only a
+/// fallback for when the process's own exit notification is lost.
+pub(crate) fn is_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        // Signal 0 only checks that the process exists; EPERM means it does
+        // but belongs to someone else.
+        signal(pid, 0) || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    #[cfg(windows)]
+    {
+        win::is_alive(pid)
+    }
+}
+
+#[cfg(unix)]
+fn signal(pid: u32, signal: libc::c_int) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    unsafe { libc::kill(pid, signal) == 0 }
+}
+
+/// Direct children of `pid`, read from `/proc`. Empty where `/proc` isn't
+/// available (macOS, which always ships `pkill`).
+#[cfg(unix)]
+fn children(pid: u32) -> Vec<u32> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|&candidate| {
+            std::fs::read_to_string(format!("/proc/{}/stat", candidate))
+                .ok()
+                // The command name is parenthesised and may contain spaces,
+                // so the parent pid is the second field after the last `)`.
+                .and_then(|stat| {
+                    let rest = &stat[stat.rfind(')')? + 1..];
+                    rest.split_whitespace().nth(1)?.parse::<u32>().ok()
+                })
+                == Some(pid)
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+mod win {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32First, Process32Next, PROCESSENTRY32, TH32CS_SNAPPROCESS,
+    };
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, TerminateProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+        PROCESS_TERMINATE,
+    };
+
+    /// Exit code `GetExitCodeProcess` reports for a running process.
+    const STILL_ACTIVE: u32 = 259;
+
+    pub(super) fn is_alive(pid: u32) -> bool {
+        // SAFETY: the handle is checked before use and closed afterwards.
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if handle.is_null() {
+                return false;
+            }
+            let mut code = 0;
+            let ok = GetExitCodeProcess(handle, &mut code);
+            CloseHandle(handle);
+            ok != 0 && code == STILL_ACTIVE
+        }
+    }
+
+    pub(super) fn terminate(pid: u32) {
+        // SAFETY: the handle is checked before use and closed afterwards.
+        unsafe {
+            let handle = OpenProcess(PROCESS_TERMINATE, 0, pid);
+            if !handle.is_null() {
+                TerminateProcess(handle, 1);
+                CloseHandle(handle);
+            }
+        }
+    }
+
+    /// Terminate `pid` and its descendants, children first.
+    pub(super) fn kill_tree(pid: u32) {
+        let processes = snapshot();
+        let mut stack = vec![pid];
+        let mut tree = Vec::new();
+        while let Some(parent) = stack.pop() {
+            // Pids can be reused, so guard against cycles.
+            if tree.contains(&parent) {
+                continue;
+            }
+            tree.push(parent);
+            stack.extend(
+                processes
+                    .iter()
+                    .filter(|&&(child, ppid)| ppid == parent && child != parent)
+                    .map(|&(child, _)| child),
+            );
+        }
+        for pid in tree.into_iter().rev() {
+            terminate(pid);
+        }
+    }
+
+    /// `(pid, parent pid)` of every running process.
+    fn snapshot() -> Vec<(u32, u32)> {
+        let mut processes = Vec::new();
+        // SAFETY: the entry is zero-initialised with its size set as the API
+        // requires, and the snapshot handle is closed afterwards.
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+            if snapshot == INVALID_HANDLE_VALUE {
+                return processes;
+            }
+            let mut entry: PROCESSENTRY32 = std::mem::zeroed();
+            entry.dwSize = std::mem::size_of::<PROCESSENTRY32>() as u32;
+            let mut ok = Process32First(snapshot, &mut entry);
+            while ok != 0 {
+                processes.push((entry.th32ProcessID, entry.th32ParentProcessID));
+                ok = Process32Next(snapshot, &mut entry);
+            }
+            CloseHandle(snapshot);
+        }
+        processes
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn output_within_collects_the_output() {
+        let mut command = StdCommand::new("sh");
+        command.args(["-c", "echo out; echo err >&2"]);
+        let output =
+            tauri::async_runtime::block_on(output_within(command, Duration::from_secs(10)))
+                .unwrap()
+                .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn output_within_kills_a_command_that_takes_too_long() {
+        let mut command = StdCommand::new("sleep");
+        command.arg("30");
+        let started = Instant::now();
+        let output =
+            tauri::async_runtime::block_on(output_within(command, Duration::from_millis(200)))
+                .unwrap();
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
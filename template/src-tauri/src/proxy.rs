@@ -0,0 +1,322 @@
+//! Forwarding frontend requests to the backend from Rust.
+//!
+//! The frontend never needs to know the port or the auth token: it hands a
+//! method, path and body to the `proxy_request` command, and the request is
+//! only ever sent to the managed backend on 127.0.0.1.
+
+use crate::http;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// How long a proxied request may take, including reading the response.
+const PROXY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default limit on a proxied response, headers included.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Headers the proxy controls itself and callers may not set.
+const RESERVED_HEADERS: [&str; 5] = [
+    "host",
+    "connection",
+    "content-length",
+    "transfer-encoding",
+    "authorization",
+];
+
+/// A request from the frontend, addressed relative to the backend.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProxyRequest {
+    pub method: String,
+    /// Path and query, e.g. `/api/items?page=2`.
+    pub path: String,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+/// The backend's response, fully buffered.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The body as text; invalid UTF-8 is replaced.
+    pub body: String,
+}
+
+/// Result of the `ping` command, in microseconds so the proxy's share
+/// doesn't round to nothing. The frontend subtracts `total_us` from its own
+/// round trip to get the IPC overhead.
+#[derive(Debug, Clone, Serialize)]
+pub struct Ping {
+    pub status: u16,
+    /// Time spent in the command, from receiving it to having the response.
+    pub total_us: u64,
+    /// Time the backend took to answer once the request was sent.
+    pub backend_us: u64,
+    /// Time spent forwarding the request besides waiting for the backend:
+    /// validating it, connecting, sending it and parsing the response.
+    /// Waiting at the gate and for the manager only count towards
+    /// `total_us`.
+    pub proxy_overhead_us: u64,
+}
+
+/// Holds proxied requests back while the backend is restarting.
+///
+/// Up to `max_waiting` requests wait for the restart to finish, each for at
+/// most `timeout`; anything beyond that gets a `503` response to retry.
+///
+/// It doesn't borrow the manager, so [`manage`](crate::app::manage) keeps
+/// one in app state for requests to pass without waiting on the manager's
+/// lock, which a restart holds.
+#[derive(Clone)]
+pub struct RequestGate {
+    pub(crate) restarting: watch::Receiver<bool>,
+    pub(crate) waiting: Arc<AtomicUsize>,
+    pub(crate) max_waiting: usize,
+    pub(crate) timeout: Duration,
+    pub(crate) draining: Arc<AtomicBool>,
+    pub(crate) in_flight: watch::Sender<usize>,
+}
+
+impl RequestGate {
+    /// Wait until the backend isn't restarting. Returns a `503` response
+    /// to hand back instead if the queue is full, the wait times out or the
+    /// backend is being shut down.
+    ///
+    /// Hold on to the returned guard until the request is done, so a
+    /// [`Drain`] waits for it.
+    pub async fn pass(mut self) -> Result<InFlight, ProxyResponse> {
+        // Count the request before checking, so a drain starting now
+        // either sees it or is seen by it
+        self.in_flight.send_modify(|n| *n += 1);
+        let in_flight = InFlight(self.in_flight.clone());
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(shutting_down());
+        }
+        if !*self.restarting.borrow() {
+            return Ok(in_flight);
+        }
+
+        if self.waiting.fetch_add(1, Ordering::SeqCst) >= self.max_waiting {
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            return Err(unavailable("too many requests waiting for the restart"));
+        }
+        let result = tokio::time::timeout(
+            self.timeout,
+            self.restarting.wait_for(|restarting| !restarting),
+        )
+        .await;
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+
+        match result {
+            Ok(Ok(_)) if self.draining.load(Ordering::SeqCst) => Err(shutting_down()),
+            Ok(Ok(_)) => Ok(in_flight),
+            Ok(Err(_)) => Err(unavailable("the API server is gone")),
+            Err(_) => Err(unavailable("the restart did not finish in time")),
+        }
+    }
+}
+
+/// A proxied request that passed the [`RequestGate`], counted until it is
+/// dropped.
+pub struct InFlight(watch::Sender<usize>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.send_modify(|n| *n -= 1);
+    }
+}
+
+/// Stops the [`RequestGate`] letting requests through and waits for the
+/// ones already past it, before a shutdown. Get one from
+/// [`SidecarManager::drainer`](crate::sidecar::SidecarManager::drainer).
+pub struct Drain {
+    pub(crate) draining: Arc<AtomicBool>,
+    pub(crate) in_flight: watch::Receiver<usize>,
+}
+
+impl Drain {
+    /// Refuse new proxied requests, then wait up to `timeout` for those in
+    /// flight. Returns whether they all finished. The gate opens again on
+    /// the next `start`.
+    pub async fn wait(mut self, timeout: Duration) -> bool {
+        self.draining.store(true, Ordering::SeqCst);
+        matches!(
+            tokio::time::timeout(timeout, self.in_flight.wait_for(|n| *n == 0)).await,
+            Ok(Ok(_))
+        )
+    }
+}
+
+/// A `503` for a request arriving during shutdown.
+fn shutting_down() -> ProxyResponse {
+    ProxyResponse {
+        status: 503,
+        headers: Vec::new(),
+        body: "API server is shutting down".into(),
+    }
+}
+
+/// A `503` telling the caller to retry shortly.
+fn unavailable(reason: &str) -> ProxyResponse {
+    ProxyResponse {
+        status: 503,
+        headers: vec![("Retry-After".into(), "1".into())],
+        body: format!("API server is restarting: {}", reason),
+    }
+}
+
+/// Send `request` to the backend on `port`, adding `token` as a bearer
+/// token if one is configured. Fails if the response is larger than
+/// `max_response_bytes`.
+pub async fn forward(
+    port: u16,
+    token: Option<&str>,
+    max_response_bytes: u64,
+    request: &ProxyRequest,
+) -> Result<ProxyResponse, String> {
+    let (response, _) = forward_timed(port, token, max_response_bytes, request).await?;
+    Ok(response)
+}
+
+/// [`forward`], also returning how long the backend took to answer.
+pub async fn forward_timed(
+    port: u16,
+    token: Option<&str>,
+    max_response_bytes: u64,
+    request: &ProxyRequest,
+) -> Result<(ProxyResponse, Duration), String> {
+    validate(request)?;
+
+    let authorization = token.map(|token| format!("Bearer {}", token));
+    let headers: Vec<(&str, &str)> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    let headers = http::control_headers(authorization.as_deref(), &headers);
+
+    let method = request.method.to_ascii_uppercase();
+    let response = http::request_limited(
+        port,
+        &method,
+        &request.path,
+        &headers,
+        request.body.as_deref().map(str::as_bytes),
+        PROXY_TIMEOUT,
+        max_response_bytes,
+    )
+    .await?;
+
+    let proxied = ProxyResponse {
+        status: response.status,
+        headers: response.headers,
+        body: String::from_utf8_lossy(&response.body).into_owned(),
+    };
+    Ok((proxied, response.waited))
+}
+
+/// Reject anything that could address another host or smuggle extra
+/// request lines.
+fn validate(request: &ProxyRequest) -> Result<(), String> {
+    if request.method.is_empty() || !request.method.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return Err(format!("Invalid HTTP method: {:?}", request.method));
+    }
+    // Origin-form only: an absolute URL or `//host` would reach elsewhere
+    // through a backend that honours it.
+    if !request.path.starts_with('/')
+        || request.path.starts_with("//")
+        || request
+            .path
+            .bytes()
+            .any(|b| b.is_ascii_whitespace() || b.is_ascii_control())
+    {
+        return Err(format!("Invalid request path: {:?}", request.path));
+    }
+    for (name, value) in &request.headers {
+        if name.is_empty()
+            || !name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        {
+            return Err(format!("Invalid header name: {:?}", name));
+        }
+        if RESERVED_HEADERS
+            .iter()
+            .any(|r| name.eq_ignore_ascii_case(r))
+        {
+            return Err(format!("Header {} is set by the proxy", name));
+        }
+        if value.bytes().any(|b| b == b'\r' || b == b'\n') {
+            return Err(format!("Invalid value for header {}", name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, headers: &[(&str, &str)]) -> ProxyRequest {
+        ProxyRequest {
+            method: method.into(),
+            path: path.into(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn validate_accepts_an_origin_form_request() {
+        let request = request("get", "/api/items?page=2", &[("X-Trace-Id", "abc")]);
+        assert_eq!(validate(&request), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_paths_that_leave_the_backend() {
+        for path in [
+            "http://example.com/",
+            "//example.com/items",
+            "items",
+            "",
+            "/items HTTP/1.1\r\nHost: example.com",
+            "/a b",
+        ] {
+            let error = validate(&request("GET", path, &[])).unwrap_err();
+            assert!(error.starts_with("Invalid request path"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_line_breaks_in_header_values() {
+        for value in ["a\r\nX-Injected: 1", "a\nb", "a\rb"] {
+            let error = validate(&request("GET", "/", &[("X-Note", value)])).unwrap_err();
+            assert_eq!(error, "Invalid value for header X-Note");
+        }
+    }
+
+    #[test]
+    fn validate_rejects_headers_the_proxy_sets() {
+        for name in ["Authorization", "host", "Transfer-Encoding"] {
+            let error = validate(&request("GET", "/", &[(name, "x")])).unwrap_err();
+            assert_eq!(error, format!("Header {} is set by the proxy", name));
+        }
+        let error = validate(&request("GET", "/", &[("X Bad", "x")])).unwrap_err();
+        assert!(error.starts_with("Invalid header name"), "{}", error);
+    }
+
+    #[test]
+    fn validate_rejects_non_alphabetic_methods() {
+        for method in ["", "GET /evil", "GET\r\n", "M-SEARCH", "P0ST"] {
+            let error = validate(&request(method, "/", &[])).unwrap_err();
+            assert!(error.starts_with("Invalid HTTP method"), "{}", error);
+        }
+    }
+}
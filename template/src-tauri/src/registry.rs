@@ -0,0 +1,453 @@
+//! Named sidecars managed together.
+
+use crate::error::SidecarError;
+use crate::sidecar::{
+    self, AppSignal, BackendStatus, RestartOutcome, ShutdownReason, SidecarManager,
+};
+use log::{error, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::sync::Mutex;
+
+/// Default time [`SidecarRegistry::shutdown`] gives `stop_all`.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default time [`SidecarRegistry::shutdown`] waits for proxied requests.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Order in which [`SidecarRegistry::stop_all`] stops sidecars.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum StopOrder {
+    /// The reverse of start order, so sidecars that others depend on are
+    /// stopped last.
+    #[default]
+    ReverseStart,
+    /// Start order.
+    Start,
+    /// Sidecars by name in the given order; unlisted ones are stopped
+    /// afterwards in reverse start order.
+    Custom(Vec<String>),
+}
+
+/// What [`SidecarRegistry::start_all`] waits for between starting one
+/// sidecar and the next, to spread out the load of booting them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartStagger {
+    /// Start the next one right away.
+    #[default]
+    None,
+    /// Wait a fixed interval.
+    Delay(Duration),
+    /// Wait until the previous one is ready, up to its startup timeout.
+    UntilReady,
+}
+
+/// One entry of [`SidecarRegistry::list`].
+#[derive(Debug, Clone, Serialize)]
+pub struct SidecarInfo {
+    pub name: String,
+    /// `None` unless the sidecar is running.
+    pub pid: Option<u32>,
+    /// Status, port and the last exit, so stopped or crashed sidecars still
+    /// show how they ended.
+    #[serde(flatten)]
+    pub status: BackendStatus,
+}
+
+/// The sidecars an app manages, by name, in registration order.
+pub struct SidecarRegistry {
+    sidecars: Vec<(String, Arc<Mutex<SidecarManager>>)>,
+    /// `(dependent, dependency)` pairs.
+    dependencies: Vec<(String, String)>,
+    stop_order: StopOrder,
+    start_stagger: StartStagger,
+    shutdown_timeout: Duration,
+    drain_timeout: Duration,
+    keep_alive_on_close: bool,
+}
+
+impl Default for SidecarRegistry {
+    fn default() -> Self {
+        Self {
+            sidecars: Vec::new(),
+            dependencies: Vec::new(),
+            stop_order: StopOrder::default(),
+            start_stagger: StartStagger::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            keep_alive_on_close: false,
+        }
+    }
+}
+
+impl SidecarRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long [`shutdown`](Self::shutdown) lets sidecars stop gracefully
+    /// before force-killing them (default 5s).
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// How long [`shutdown`](Self::shutdown) waits for in-flight proxied
+    /// requests before stopping the sidecars (default 5s). Zero skips
+    /// waiting but still refuses new requests.
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Keep the sidecars running when the window is closed, hiding it
+    /// instead, for tray or daemon-style apps. They are then only stopped by
+    /// the `quit_app` command, so the app must offer a way to invoke it
+    /// (e.g. a tray menu). Off by default.
+    pub fn with_keep_alive_on_close(mut self, keep_alive: bool) -> Self {
+        self.keep_alive_on_close = keep_alive;
+        self
+    }
+
+    /// Whether closing the window leaves the sidecars running.
+    pub fn keep_alive_on_close(&self) -> bool {
+        self.keep_alive_on_close
+    }
+
+    /// Add a sidecar. Sidecars are started in the order they are added,
+    /// [dependencies](Self::with_dependency) permitting.
+    pub fn with_sidecar(
+        mut self,
+        name: impl Into<String>,
+        manager: Arc<Mutex<SidecarManager>>,
+    ) -> Self {
+        self.sidecars.push((name.into(), manager));
+        self
+    }
+
+    /// Declare that `dependent` uses `dependency`, so
+    /// [`start_all`](Self::start_all) starts `dependency` first and, with
+    /// [`StopOrder::ReverseStart`], [`stop_all`](Self::stop_all) stops it
+    /// last. Both must already be added.
+    ///
+    /// Fails if either isn't registered or the edge would close a cycle.
+    pub fn with_dependency(
+        mut self,
+        dependent: impl Into<String>,
+        dependency: impl Into<String>,
+    ) -> Result<Self, SidecarError> {
+        let (dependent, dependency) = (dependent.into(), dependency.into());
+        for name in [&dependent, &dependency] {
+            if self.get(name).is_none() {
+                return Err(format!("No sidecar named {:?} is registered", name).into());
+            }
+        }
+        self.dependencies.push((dependent, dependency));
+        if let Err(cycle) = self.start_indices() {
+            let (dependent, dependency) = self.dependencies.pop().unwrap_or_default();
+            return Err(format!(
+                "{} depending on {} creates a dependency cycle between {}",
+                dependent,
+                dependency,
+                cycle.join(", ")
+            )
+            .into());
+        }
+        Ok(self)
+    }
+
+    /// Choose the order `stop_all` uses (default [`StopOrder::ReverseStart`]).
+    pub fn with_stop_order(mut self, order: StopOrder) -> Self {
+        self.stop_order = order;
+        self
+    }
+
+    /// Choose what `start_all` and `restart_all` wait for between sidecars
+    /// (default [`StartStagger::None`]).
+    pub fn with_start_stagger(mut self, stagger: StartStagger) -> Self {
+        self.start_stagger = stagger;
+        self
+    }
+
+    /// The sidecar registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&Arc<Mutex<SidecarManager>>> {
+        self.sidecars
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, manager)| manager)
+    }
+
+    /// Names of all sidecars, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sidecars.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// A snapshot of every sidecar, in registration order. Each entry is read
+    /// under that sidecar's lock, so its fields are consistent with each
+    /// other.
+    pub async fn list(&self) -> Vec<SidecarInfo> {
+        let mut sidecars = Vec::with_capacity(self.sidecars.len());
+        for (name, manager) in &self.sidecars {
+            let manager = manager.lock().await;
+            sidecars.push(SidecarInfo {
+                name: name.clone(),
+                pid: manager.pid(),
+                status: manager.status(),
+            });
+        }
+        sidecars
+    }
+
+    /// Start every sidecar, dependencies first. Stops at the first failure,
+    /// so nothing is started without the sidecars it depends on.
+    pub async fn start_all(&self, app: &AppHandle) -> Result<(), SidecarError> {
+        let sequence = self.start_sequence();
+        for (i, (name, manager)) in sequence.iter().enumerate() {
+            if let Err(e) = manager.lock().await.start(app).await {
+                return Err(format!("Failed to start sidecar {}: {}", name, e).into());
+            }
+            if i + 1 < sequence.len() {
+                self.stagger(name, manager).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop every sidecar in the configured order.
+    ///
+    /// A failing stop doesn't prevent the others; all failures are
+    /// reported together.
+    pub async fn stop_all(&self) -> Result<(), SidecarError> {
+        self.stop_all_for(ShutdownReason::Requested).await
+    }
+
+    /// [`stop_all`](Self::stop_all), telling each sidecar `reason`.
+    async fn stop_all_for(&self, reason: ShutdownReason) -> Result<(), SidecarError> {
+        let mut failures = Vec::new();
+        for (name, manager) in self.stop_sequence() {
+            if let Err(e) = manager.lock().await.stop_for(reason).await {
+                failures.push(format!("{}: {}", name, e));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Failed to stop sidecars: {}", failures.join("; ")).into())
+        }
+    }
+
+    /// Restart the whole stack: stop every sidecar in stop order, then start
+    /// them all again dependencies first. A sidecar that fails to stop or
+    /// start doesn't hold up the others; each one's outcome is reported by
+    /// name, with the error as its message.
+    pub async fn restart_all(&self, app: &AppHandle) -> HashMap<String, RestartOutcome> {
+        let mut errors: HashMap<&str, String> = HashMap::new();
+        for (name, manager) in self.stop_sequence() {
+            if let Err(e) = manager.lock().await.stop_for(ShutdownReason::Restart).await {
+                errors.insert(name, format!("Failed to stop: {}", e));
+            }
+        }
+
+        let mut outcomes = HashMap::with_capacity(self.sidecars.len());
+        let sequence = self.start_sequence();
+        for (i, (name, manager)) in sequence.iter().enumerate() {
+            if i > 0 {
+                let (previous, previous_manager) = sequence[i - 1];
+                self.stagger(previous, previous_manager).await;
+            }
+            let mut manager = manager.lock().await;
+            let started = manager.start(app).await;
+            let message = match (errors.remove(name.as_str()), started) {
+                (None, Ok(message)) => message,
+                (None, Err(e)) => format!("Failed to start: {}", e),
+                (Some(stop_error), Ok(_)) => stop_error,
+                (Some(stop_error), Err(e)) => {
+                    format!("{}; failed to start: {}", stop_error, e)
+                }
+            };
+            let status = manager.status();
+            outcomes.insert(
+                name.clone(),
+                RestartOutcome {
+                    status: status.status,
+                    port: status.port,
+                    message,
+                },
+            );
+        }
+        outcomes
+    }
+
+    /// Apply each sidecar's [signal action](SidecarManager::with_signal_action)
+    /// for `signal`, in stop order, skipping sidecars that don't forward
+    /// signals. Failures are logged.
+    pub async fn handle_signal(&self, signal: AppSignal) {
+        for (name, manager) in self.stop_sequence() {
+            let mut manager = manager.lock().await;
+            if !manager.forwards_signals() {
+                continue;
+            }
+            if let Err(e) = manager.handle_app_signal(signal).await {
+                error!("Error stopping sidecar {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Refuse new proxied requests to every sidecar and wait up to `timeout`
+    /// for the ones in flight. Returns whether they all finished.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        // Bounded as a whole, since a wedged manager holds its lock
+        let drain_all = async {
+            let mut drained = true;
+            for (_, manager) in &self.sidecars {
+                let drain = manager.lock().await.drainer();
+                drained &= drain.wait(timeout).await;
+            }
+            drained
+        };
+        let drained = tokio::time::timeout(timeout, drain_all)
+            .await
+            .unwrap_or(false);
+        if !drained {
+            warn!(
+                "Proxied requests still running after {:?}, stopping anyway",
+                timeout
+            );
+        }
+        drained
+    }
+
+    /// [`drain`](Self::drain), then [`stop_all`](Self::stop_all),
+    /// force-killing every sidecar if it takes longer than the shutdown
+    /// timeout. Always returns in bounded time, so the app can exit even if
+    /// a backend refuses to.
+    pub async fn shutdown(&self) -> Result<(), SidecarError> {
+        self.drain(self.drain_timeout).await;
+
+        // Grab the PIDs first: a wedged stop holds the process handle
+        let mut pids = Vec::new();
+        for (_, manager) in &self.sidecars {
+            if let Ok(manager) = manager.try_lock() {
+                pids.extend(manager.pid());
+            }
+        }
+
+        let stopped = self.stop_all_for(ShutdownReason::AppExit);
+        match tokio::time::timeout(self.shutdown_timeout, stopped).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!(
+                    "Sidecars did not stop within {:?}, forcing shutdown",
+                    self.shutdown_timeout
+                );
+                for pid in pids {
+                    sidecar::force_kill(pid);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait as [configured](Self::with_start_stagger) after starting `name`.
+    async fn stagger(&self, name: &str, manager: &Mutex<SidecarManager>) {
+        match self.start_stagger {
+            StartStagger::None => {}
+            StartStagger::Delay(delay) => tokio::time::sleep(delay).await,
+            StartStagger::UntilReady => {
+                // Not under the lock, so the sidecar stays usable meanwhile
+                let (ready, timeout) = {
+                    let manager = manager.lock().await;
+                    (manager.running_ready_check(), manager.startup_timeout())
+                };
+                if let Some(ready) = ready {
+                    if let Err(e) = ready.wait(timeout).await {
+                        warn!(
+                            "Sidecar {} did not become ready, starting the next: {}",
+                            name, e
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sidecars in registration order, moved after whatever they depend on.
+    fn start_sequence(&self) -> Vec<&(String, Arc<Mutex<SidecarManager>>)> {
+        // Cycles are rejected by with_dependency
+        let indices = self
+            .start_indices()
+            .unwrap_or_else(|_| (0..self.sidecars.len()).collect());
+        indices.into_iter().map(|i| &self.sidecars[i]).collect()
+    }
+
+    /// Indices of the sidecars in start order, or the names of those caught
+    /// in a dependency cycle.
+    fn start_indices(&self) -> Result<Vec<usize>, Vec<String>> {
+        let index = |name: &str| self.sidecars.iter().position(|(n, _)| n == name);
+        let edges: Vec<(usize, usize)> = self
+            .dependencies
+            .iter()
+            .filter_map(|(dependent, dependency)| Some((index(dependent)?, index(dependency)?)))
+            .collect();
+        let mut started = vec![false; self.sidecars.len()];
+        let mut order = Vec::with_capacity(self.sidecars.len());
+        while order.len() < self.sidecars.len() {
+            // The first sidecar, in registration order, whose dependencies
+            // are all started
+            let next = (0..self.sidecars.len()).find(|&i| {
+                !started[i]
+                    && edges
+                        .iter()
+                        .all(|&(dependent, dependency)| dependent != i || started[dependency])
+            });
+            let Some(next) = next else {
+                return Err((0..self.sidecars.len())
+                    .filter(|&i| !started[i])
+                    .map(|i| self.sidecars[i].0.clone())
+                    .collect());
+            };
+            started[next] = true;
+            order.push(next);
+        }
+        Ok(order)
+    }
+
+    /// Sidecars in the order they should be stopped.
+    fn stop_sequence(&self) -> Vec<&(String, Arc<Mutex<SidecarManager>>)> {
+        let start = self.start_sequence();
+        let reverse: Vec<_> = start.iter().rev().copied().collect();
+        match &self.stop_order {
+            StopOrder::ReverseStart => reverse,
+            StopOrder::Start => start,
+            StopOrder::Custom(names) => {
+                let mut sequence: Vec<_> = names
+                    .iter()
+                    .filter_map(|name| self.sidecars.iter().find(|(n, _)| n == name))
+                    .collect();
+                for sidecar in reverse {
+                    if !names.contains(&sidecar.0) {
+                        sequence.push(sidecar);
+                    }
+                }
+                sequence
+            }
+        }
+    }
+}
+
+impl Drop for SidecarRegistry {
+    fn drop(&mut self) {
+        // Force-kill whatever is still running; managers that are locked
+        // elsewhere stop themselves when their last handle is dropped.
+        for (name, manager) in self.stop_sequence() {
+            if let Ok(mut manager) = manager.try_lock() {
+                if let Err(e) = manager.stop() {
+                    error!("Error stopping sidecar {}: {}", name, e);
+                }
+            }
+        }
+    }
+}
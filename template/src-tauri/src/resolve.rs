@@ -0,0 +1,72 @@
+//! Where sidecar resolution looks for the backend binary.
+//!
+//! `tauri-plugin-shell` only ever spawns `<exe dir>/<name>` (plus `.exe` on
+//! Windows); `tauri build`/`tauri dev` copy `binaries/<name>-<target>` there.
+//! The remaining candidates are the usual places the binary ends up when
+//! that copy didn't happen, listed to make "sidecar not found" debuggable.
+
+use serde::Serialize;
+use std::env::consts::EXE_SUFFIX;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// Target triple the app was built for, used in sidecar file names.
+const TARGET_TRIPLE: &str = env!("TAURI_ENV_TARGET_TRIPLE");
+
+/// A path sidecar resolution would consider.
+#[derive(Debug, Clone, Serialize)]
+pub struct SidecarCandidate {
+    pub path: PathBuf,
+    pub exists: bool,
+    /// Whether this is the path that is actually spawned.
+    pub spawned: bool,
+    pub description: String,
+}
+
+/// Candidate locations for the sidecar `binary`, in lookup order.
+pub fn sidecar_candidates(app: &AppHandle, binary: &str) -> Vec<SidecarCandidate> {
+    let plain = format!("{}{}", binary, EXE_SUFFIX);
+    let suffixed = format!("{}-{}{}", binary, TARGET_TRIPLE, EXE_SUFFIX);
+    let mut candidates = Vec::new();
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from));
+    if let Some(dir) = exe_dir {
+        candidates.push((dir.join(&plain), true, "next to the app executable"));
+        candidates.push((
+            dir.join(&suffixed),
+            false,
+            "next to the app executable, target-suffixed (not copied/renamed)",
+        ));
+    }
+
+    if let Ok(dir) = app.path().resource_dir() {
+        candidates.push((dir.join(&plain), false, "app resource directory"));
+        candidates.push((
+            dir.join("binaries").join(&suffixed),
+            false,
+            "app resource directory, binaries/ subfolder",
+        ));
+    }
+
+    if cfg!(debug_assertions) {
+        candidates.push((
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("binaries")
+                .join(&suffixed),
+            false,
+            "dev build output (src-tauri/binaries)",
+        ));
+    }
+
+    candidates
+        .into_iter()
+        .map(|(path, spawned, description)| SidecarCandidate {
+            exists: path.exists(),
+            path,
+            spawned,
+            description: description.to_string(),
+        })
+        .collect()
+}
@@ -1,110 +1,6537 @@
 //! Python sidecar process management.
 
-use std::process::Command as StdCommand;
-use tauri::AppHandle;
-use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use crate::cgroup::{Cgroup, CgroupLimits};
+use crate::config_watch::{ConfigWatch, WatchAction, DEFAULT_DEBOUNCE};
+use crate::error::SidecarError;
+use crate::logs::{
+    self, now_ms, LogBuffer, LogEntry, LogFilter, LogLevel, LogLevels, LogPage, LogStream,
+    RecentLogs, DEFAULT_LOG_BYTE_BUDGET, DEFAULT_LOG_CAPACITY,
+};
+use crate::presets::{SidecarPreset, PYTHON_ENV_DEFAULTS};
+use crate::proxy::{Drain, RequestGate, DEFAULT_MAX_RESPONSE_BYTES};
+use crate::resolve::{self, SidecarCandidate};
+use crate::storage::{self, Storage};
+use crate::{http, process, stats};
+use log::{debug, error, info, trace, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::OsString;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream as StdTcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command as StdCommand, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent, TerminatedPayload};
 use tauri_plugin_shell::ShellExt;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, watch, Mutex, Notify};
+
+/// Attempts made to spawn the sidecar when the OS reports a temporary
+/// failure, and the delay before the first retry.
+const DEFAULT_SPAWN_ATTEMPTS: u32 = 3;
+const DEFAULT_SPAWN_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// How long a new instance gets to become ready by default.
+const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Range accepted by [`SidecarManager::set_startup_timeout`].
+const MIN_STARTUP_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_STARTUP_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Name of the sidecar binary listed under `bundle.externalBin`.
+const DEFAULT_BINARY: &str = "api";
+
+/// Name a sidecar goes by unless given one with
+/// [`SidecarManager::with_name`].
+const DEFAULT_NAME: &str = "api";
+
+/// Default minimum time between user-initiated restarts.
+const DEFAULT_RESTART_COOLDOWN: Duration = Duration::from_secs(2);
+
+/// How long the backend has to stay up before crash restart attempts are
+/// forgotten.
+const DEFAULT_STABILITY_WINDOW: Duration = Duration::from_secs(60);
+
+/// How long `stop` waits for a killed sidecar to be reaped.
+const REAP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Startup time assumed for progress estimates until one has been seen.
+const DEFAULT_TYPICAL_STARTUP: Duration = Duration::from_secs(5);
+
+/// How often `backend-startup-progress` is emitted while starting.
+const STARTUP_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Weight of the latest startup in the persisted typical startup time.
+const STARTUP_HISTORY_WEIGHT: f64 = 0.3;
+
+/// How long a sidecar gets to exit after an app signal is forwarded to it
+/// as [`SignalAction::Send`], before the app exits regardless.
+const FORWARDED_SIGNAL_GRACE: Duration = Duration::from_secs(5);
+
+/// How long a stack dumper may run.
+const STACK_DUMP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long [`SidecarManager::collect_dump`] waits for the backend to
+/// finish writing its dump.
+const BACKEND_DUMP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long the warmup request may take.
+const WARMUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many proxied requests may wait for a restart, and for how long.
+const DEFAULT_REQUEST_QUEUE: usize = 16;
+const DEFAULT_REQUEST_QUEUE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default pattern for the stderr line reported as a startup failure's
+/// reason.
+const DEFAULT_ERROR_PATTERN: &str = "Error|Exception|Traceback";
+
+/// Number of stderr lines kept in a startup failure.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Most log events held back while a new instance isn't ready yet; older
+/// lines are only kept in the log buffer.
+const MAX_HELD_LOG_EVENTS: usize = DEFAULT_LOG_CAPACITY;
+
+/// Held log events emitted per [`LOG_RELEASE_INTERVAL`] once the backend is
+/// ready.
+const LOG_RELEASE_BATCH: usize = 100;
+const LOG_RELEASE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Levels accepted by [`SidecarManager::set_log_level`], as Python's
+/// `logging` names them.
+pub const LOG_LEVELS: [&str; 5] = ["debug", "info", "warning", "error", "critical"];
+
+/// Events a [`SidecarManager::subscribe`]r may fall behind by before it
+/// starts missing them.
+const LIFECYCLE_CHANNEL_CAPACITY: usize = 16;
+
+/// Default number of status transitions kept for `get_status_history`.
+const DEFAULT_STATUS_HISTORY: usize = 100;
+
+/// Output lines a [`SidecarManager::subscribe_logs`] receiver may fall
+/// behind by before it starts missing them.
+const LOG_CHANNEL_CAPACITY: usize = 1024;
+
+/// Default time between health monitor ticks.
+const DEFAULT_MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Consecutive failed health probes after which the backend counts as
+/// unhealthy.
+const UNHEALTHY_AFTER_FAILURES: u32 = 3;
+
+/// How long a health probe / RSS sample is reused by `metrics()`, so the UI
+/// can poll every second without hammering the backend.
+const METRICS_CACHE_TTL: Duration = Duration::from_secs(1);
+
+/// How `restart` replaces the running sidecar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestartMode {
+    /// Stop the old process, then start a new one on the same port.
+    #[default]
+    StopFirst,
+    /// Start a new process on a fresh port, wait until it accepts
+    /// connections, switch `port()` over and emit `backend-port-changed`,
+    /// then stop the old process. The old one keeps serving, and the status
+    /// stays `running`, until the switch; restart through
+    /// [`SidecarManager::restart_shared`] so the manager isn't locked
+    /// meanwhile either.
+    ///
+    /// Both instances run side by side for a short while, so only use this
+    /// if the backend tolerates a second copy of itself sharing external
+    /// resources (model files, databases, lock files).
+    Overlap,
+}
+
+/// Whether the sidecar is a long-lived server or a job that runs to
+/// completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunMode {
+    /// `start` returns once the process is spawned and it is kept running.
+    #[default]
+    Server,
+    /// `start` waits for the process to exit and reports how it did. Auto
+    /// restart and the health monitor are disabled.
+    OneShot,
+}
+
+/// How the sidecar's stdin, stdout and stderr are connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SidecarStdio {
+    /// Pipe output through the manager, which records it, emits log events
+    /// and watches for the ready pattern.
+    #[default]
+    Piped,
+    /// Let the backend share the app's terminal, keeping colors and
+    /// interactive prompts. Meant for local debugging: nothing is recorded
+    /// or forwarded, `write_stdin` fails, and a ready pattern can't be seen,
+    /// so readiness falls back to waiting for the port to accept
+    /// connections.
+    Inherit,
+}
+
+/// One step of the shutdown sequence walked by
+/// [`SidecarManager::stop_async`].
+///
+/// Each step waits up to its `grace` period for the process to exit before
+/// moving on to the next one. If the process is still alive once the list
+/// is exhausted it is force-killed, so `stop_async` always stops it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownStep {
+    /// `POST` to `path` on the backend, asking it to shut itself down. The
+    /// body is `{"reason": ...}` with the [`ShutdownReason`].
+    HttpShutdown { path: String, grace: Duration },
+    /// Send SIGTERM. On Windows this is a `taskkill` without `/F`.
+    Sigterm { grace: Duration },
+    /// Force-kill the process tree.
+    Sigkill,
+}
+
+impl ShutdownStep {
+    /// Name used in [`ShutdownReport`].
+    fn name(&self) -> &'static str {
+        match self {
+            Self::HttpShutdown { .. } => "http_shutdown",
+            Self::Sigterm { .. } => "sigterm",
+            Self::Sigkill => "sigkill",
+        }
+    }
+}
+
+/// How long one [`ShutdownStep`] of a shutdown took, including its grace
+/// period.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownStepTiming {
+    pub step: &'static str,
+    pub elapsed_ms: u64,
+}
+
+/// How a shutdown went. Returned by the `test_shutdown` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownReport {
+    /// The steps that ran, in order. A final force-kill is listed as
+    /// `sigkill` even if the configured steps don't include it.
+    pub steps: Vec<ShutdownStepTiming>,
+    /// The step the backend exited during, or `already_exited` if it was
+    /// gone before the first one.
+    pub terminated_by: &'static str,
+    pub total_ms: u64,
+    /// HTTP shutdown requests sent, retries included.
+    pub shutdown_requests: u32,
+}
+
+/// File name of the backend's own log, inside the app log directory.
+const BACKEND_LOG_FILE_NAME: &str = "backend.log";
+
+/// How the backend is told where to write its own log file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendLogFile {
+    /// Pass the path as `<flag> <path>`, e.g. `--log-file`.
+    Flag(String),
+    /// Set the path in an environment variable, e.g. `LOG_FILE`.
+    Env(String),
+}
+
+/// Which of the app's environment variables the sidecar inherits, see
+/// [`SidecarManager::with_env_inheritance`]. Variables set on the manager
+/// are passed either way.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum EnvInheritance {
+    /// Inherit the app's whole environment.
+    #[default]
+    Inherit,
+    /// Start from an empty environment.
+    Clear,
+    /// Inherit only the named variables.
+    Allowlist(Vec<String>),
+}
+
+impl EnvInheritance {
+    /// Whether the app's `name` variable reaches the sidecar.
+    fn passes(&self, name: &str) -> bool {
+        match self {
+            Self::Inherit => true,
+            Self::Clear => false,
+            Self::Allowlist(names) => names.iter().any(|allowed| allowed == name),
+        }
+    }
+
+    /// The app's variables to pass on after clearing the environment, or
+    /// `None` if it shouldn't be cleared.
+    fn inherited(&self) -> Option<Vec<(String, OsString)>> {
+        match self {
+            Self::Inherit => None,
+            Self::Clear => Some(Vec::new()),
+            Self::Allowlist(names) => Some(
+                names
+                    .iter()
+                    .filter_map(|name| Some((name.clone(), std::env::var_os(name)?)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// How the sidecar's `PATH` is built. Relative directories are resolved
+/// against the app's resource directory, so bundled helper binaries can be
+/// found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchPath {
+    /// Put the directories in front of the inherited `PATH` (or the one set
+    /// with [`with_env`](SidecarManager::with_env)).
+    Prepend(Vec<PathBuf>),
+    /// Use only the directories.
+    Replace(Vec<PathBuf>),
+}
+
+/// Who owns the backend process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ownership {
+    /// We spawned the sidecar and are responsible for stopping it.
+    Spawned,
+    /// The backend was started outside the app (e.g. `pnpm dev:py`) and is
+    /// only attached to. We never kill it.
+    External,
+}
+
+/// A termination signal the app received, see
+/// [`SidecarManager::with_signal_action`]. On Windows, Ctrl+C is
+/// `Interrupt` and the other console events are `Terminate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppSignal {
+    Terminate,
+    Interrupt,
+}
+
+/// A signal sent to the sidecar process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildSignal {
+    /// SIGTERM; `taskkill` on Windows.
+    Terminate,
+    /// SIGINT. Windows can't send it to one process, so it terminates
+    /// instead.
+    Interrupt,
+    /// SIGHUP. Windows has no equivalent, so it terminates instead.
+    Hangup,
+}
+
+/// What a sidecar does when the app receives an [`AppSignal`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignalAction {
+    /// Walk the shutdown steps, like `stop_backend`.
+    #[default]
+    Stop,
+    /// Send the sidecar this signal and give it a few seconds to exit.
+    Send(ChildSignal),
+    /// Leave the sidecar alone, e.g. for one stopped by another sidecar or
+    /// by the registry's shutdown.
+    Ignore,
+}
+
+/// How `start` decides that a new instance is ready, see
+/// [`SidecarManager::with_readiness_check`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadinessCheck {
+    /// Wait for the ready pattern if one is set, else for the port to
+    /// accept connections.
+    #[default]
+    Probe,
+    /// Make no connections at all: the instance counts as ready `delay`
+    /// after it is spawned, there are no health, version, metrics or
+    /// [identity](SidecarManager::with_identity_check) requests and the
+    /// health monitor only notices crashes.
+    None { delay: Duration },
+}
+
+/// What `start` does when another instance of the app already manages the
+/// sidecar, see [`SidecarManager::with_single_instance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SingleInstance {
+    /// Fail with [`SidecarError::AlreadyManaged`].
+    Fail,
+    /// Attach to the other instance's backend as if it were
+    /// [external](Ownership::External), failing with
+    /// [`SidecarError::AlreadyManaged`] if it hasn't reported a port yet.
+    Attach,
+}
+
+/// Lifecycle state of the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarStatus {
+    Stopped,
+    Running,
+    /// The [fallback binary](SidecarManager::with_fallback_binary) is
+    /// running because the primary one failed to start.
+    RunningFallback,
+    /// Being replaced by a new instance, which isn't ready yet.
+    Restarting,
+    /// Something else answers on the backend's port, see
+    /// [`SidecarManager::with_identity_check`]. Requests aren't sent there.
+    Compromised,
+    /// [Maintenance mode](SidecarManager::set_maintenance_mode) is on, so
+    /// nothing is restarted automatically. The process may or may not be
+    /// up; requests still reach it while it is.
+    Maintenance,
+}
+
+impl SidecarStatus {
+    /// Whether a backend process is up, primary or fallback.
+    pub fn is_running(self) -> bool {
+        matches!(self, Self::Running | Self::RunningFallback)
+    }
+}
+
+/// Whether proxied requests are let through, which can differ from the
+/// [`SidecarStatus`] while the process is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestState {
+    /// New requests are sent as they come.
+    Open,
+    /// New requests are held until the next instance is ready.
+    Gated,
+    /// New requests are turned away while the requests in flight finish,
+    /// ahead of a shutdown.
+    Draining,
+}
+
+/// Snapshot returned by the `get_backend_status` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendStatus {
+    pub status: SidecarStatus,
+    pub requests: RequestState,
+    /// Proxied requests currently waiting on the backend.
+    pub in_flight_requests: usize,
+    /// See [`SidecarManager::set_maintenance_mode`].
+    pub maintenance: bool,
+    pub port: u16,
+    pub ownership: Ownership,
+    /// Name of the sidecar binary currently in use.
+    pub binary: String,
+    /// Version reported by the backend's version endpoint once it is ready.
+    pub backend_version: Option<String>,
+    /// How the most recent sidecar process exited.
+    pub last_exit: Option<SidecarExit>,
+    /// Message registered with [`SidecarManager::with_exit_message`] for
+    /// the exit code of a crashed process.
+    pub last_exit_message: Option<String>,
+    /// Whether the app data directory accepts files (port files, persisted
+    /// logs, crash dumps). `None` until the first `start`.
+    pub storage_writable: Option<bool>,
+}
+
+/// How a sidecar process exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SidecarExit {
+    /// Exit code, if the process exited on its own.
+    pub code: Option<i32>,
+    /// Signal that terminated the process (Unix only).
+    pub signal: Option<i32>,
+    /// Whether this was the result of us stopping it. Anything else is
+    /// counted as a crash.
+    pub clean: bool,
+}
+
+impl SidecarExit {
+    /// Normalize a `Terminated` payload. `stopping` is whether we had asked
+    /// the process to stop, `awaited` whether someone was
+    /// [`wait`](SidecarManager::wait)ing for it to finish on its own.
+    fn new(payload: TerminatedPayload, stopping: bool, awaited: bool) -> Self {
+        // SIGKILL and SIGTERM, the only signals `stop` sends.
+        const STOP_SIGNALS: [i32; 2] = [9, 15];
+        let expected = match (payload.code, payload.signal) {
+            (_, Some(signal)) => STOP_SIGNALS.contains(&signal),
+            // An HTTP shutdown exits normally; taskkill leaves an exit code
+            // of 1 on Windows.
+            (Some(code), None) => code == 0 || cfg!(windows),
+            (None, None) => false,
+        };
+        Self {
+            code: payload.code,
+            signal: payload.signal,
+            clean: (stopping && expected) || (awaited && payload.code == Some(0)),
+        }
+    }
+}
+
+impl std::fmt::Display for SidecarExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.code, self.signal) {
+            (_, Some(signal)) => write!(f, "terminated by signal {}", signal)?,
+            (Some(code), None) => write!(f, "exited with code {}", code)?,
+            (None, None) => write!(f, "exited with unknown status")?,
+        }
+        if !self.clean {
+            write!(f, " (crash)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returned by the `restart_backend` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestartOutcome {
+    pub status: SidecarStatus,
+    /// Port after the restart, which may have changed.
+    pub port: u16,
+    /// Human-readable summary.
+    pub message: String,
+}
+
+/// Snapshot returned by the `get_backend_metrics` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendMetrics {
+    pub status: SidecarStatus,
+    pub uptime_secs: u64,
+    pub restart_count: u32,
+    pub crash_count: u32,
+    pub last_probe_latency_ms: Option<u64>,
+    pub rss_bytes: Option<u64>,
+    /// Readiness and health probes sent since the app started.
+    pub probe_attempts: u64,
+    pub probe_failures: u64,
+    /// Instances that became ready since the app started.
+    pub successful_startups: u64,
+    /// Restarts after a crash since the app started.
+    pub crash_restarts: u64,
+    /// How long the latest warmup request took, if one succeeded.
+    pub warmup_latency_ms: Option<u64>,
+}
+
+/// Outcome of one check run by [`SidecarManager::validate`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Report returned by the `validate_backend` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    /// Whether every check passed.
+    pub ok: bool,
+    pub checks: Vec<ValidationCheck>,
+}
+
+/// Fingerprint of the sidecar binary, returned by the
+/// `get_sidecar_binary_info` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryInfo {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the file contents.
+    pub sha256: String,
+    /// Modification time in Unix milliseconds, if the platform reports one.
+    pub modified_ms: Option<u64>,
+}
+
+/// A cached health probe and RSS reading.
+struct MetricsSample {
+    taken_at: Instant,
+    probe_latency_ms: Option<u64>,
+    rss_bytes: Option<u64>,
+}
+
+/// Totals kept for the lifetime of the manager, shared with the tasks that
+/// probe the backend.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    probe_attempts: AtomicU64,
+    probe_failures: AtomicU64,
+    successful_startups: AtomicU64,
+    crash_restarts: AtomicU64,
+}
+
+impl Counters {
+    fn record_probe(&self, ok: bool) {
+        self.probe_attempts.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.probe_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Default prefix applied to every emitted event name.
+pub const DEFAULT_EVENT_NAMESPACE: &str = "tether:";
+
+/// Names of the events the manager emits, after applying the namespace.
+///
+/// Returned by the `get_event_names` command so the frontend can subscribe
+/// without hard-coding them.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventNames {
+    /// A line of sidecar output, with a [`LogEntry`] payload.
+    pub log: String,
+    /// The backend moved to a new port, with a [`PortChanged`] payload.
+    pub port_changed: String,
+    /// The backend was restarted for exceeding its memory limit, with a
+    /// [`MemoryLimitExceeded`] payload.
+    pub restarted_oom: String,
+    /// The backend was relaunched with a different binary, with a
+    /// [`BinaryChanged`] payload.
+    pub binary_changed: String,
+    /// A crashed backend is about to be restarted, with a [`RestartAttempt`]
+    /// payload.
+    pub restart_attempt: String,
+    /// The backend failed several health probes in a row, with an
+    /// [`Unhealthy`] payload.
+    pub unhealthy: String,
+    /// The backend was stopped for reaching its maximum runtime, with a
+    /// [`RuntimeExpired`] payload.
+    pub runtime_expired: String,
+    /// A starting backend accepted its first TCP connection, though it may
+    /// not be healthy yet, with a [`BackendConnecting`] payload.
+    pub connecting: String,
+    /// The auth token was replaced, with a [`TokenRotated`] payload.
+    pub token_rotated: String,
+    /// A server that isn't ours answered on the backend's port, with an
+    /// [`IdentityMismatch`] payload.
+    pub identity_mismatch: String,
+    /// Estimated progress of a backend that is starting, with a
+    /// [`StartupProgress`] payload.
+    pub startup_progress: String,
+}
+
+impl EventNames {
+    fn new(namespace: &str) -> Self {
+        Self {
+            log: format!("{}sidecar-log", namespace),
+            port_changed: format!("{}backend-port-changed", namespace),
+            restarted_oom: format!("{}backend-restarted-oom", namespace),
+            binary_changed: format!("{}backend-binary-changed", namespace),
+            restart_attempt: format!("{}backend-restart-attempt", namespace),
+            connecting: format!("{}backend-connecting", namespace),
+            runtime_expired: format!("{}backend-runtime-expired", namespace),
+            unhealthy: format!("{}backend-unhealthy", namespace),
+            token_rotated: format!("{}token-rotated", namespace),
+            identity_mismatch: format!("{}backend-identity-mismatch", namespace),
+            startup_progress: format!("{}backend-startup-progress", namespace),
+        }
+    }
+}
+
+/// Payload of the `backend-restarted-oom` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryLimitExceeded {
+    pub rss_bytes: u64,
+    pub max_rss_bytes: u64,
+}
+
+/// How a crashed sidecar is brought back, see
+/// [`SidecarManager::with_auto_restart`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoRestart {
+    pub max_attempts: u32,
+    /// Delay before the first attempt.
+    pub initial_delay: Duration,
+    /// Factor the delay is multiplied by after every failed attempt.
+    pub multiplier: u32,
+    /// Longest delay between attempts, if capped.
+    pub max_delay: Option<Duration>,
+    /// Up to this much is added to every delay at random, so sidecars that
+    /// crashed together don't restart in lockstep.
+    pub jitter: Duration,
+}
+
+impl AutoRestart {
+    /// Delay before the attempt that follows `failures` failed ones.
+    pub(crate) fn delay(&self, failures: u32) -> Duration {
+        let delay = self
+            .initial_delay
+            .saturating_mul(self.multiplier.saturating_pow(failures));
+        let delay = self.max_delay.map_or(delay, |max| delay.min(max));
+        let jitter_ms = self.jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            return delay;
+        }
+        let mut random = [0u8; 8];
+        let _ = getrandom::getrandom(&mut random);
+        delay + Duration::from_millis(u64::from_le_bytes(random) % (jitter_ms + 1))
+    }
+}
+
+/// Backoff settings taken by the `set_restart_backoff` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RestartBackoff {
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub multiplier: u32,
+    pub max_delay_ms: Option<u64>,
+    #[serde(default)]
+    pub jitter_ms: u64,
+}
+
+/// Longest delay [`RestartBackoff`] accepts, for both the initial and the
+/// maximum delay and the jitter.
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(3600);
+
+/// Returned by the `get_auto_restart` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoRestartInfo {
+    /// Whether crashes are currently recovered from; see
+    /// [`SidecarManager::set_auto_restart`].
+    pub enabled: bool,
+    pub max_attempts: u32,
+    /// Attempts made since the backend was last stable for the stability
+    /// window.
+    pub attempts_used: u32,
+    pub initial_delay_ms: u64,
+    /// Factor the delay is multiplied by after every failed attempt.
+    pub backoff_factor: u32,
+    pub max_delay_ms: Option<u64>,
+    pub jitter_ms: u64,
+}
+
+/// Payload of the `backend-restart-attempt` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestartAttempt {
+    pub attempt: u32,
+    pub max: u32,
+    pub delay_ms: u64,
+}
+
+/// Payload of the `backend-binary-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryChanged {
+    pub old_binary: String,
+    pub binary: String,
+}
+
+/// An extra port the backend listens on, see
+/// [`SidecarManager::with_named_port`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NamedPort {
+    name: String,
+    arg: String,
+    /// Requested port, or `None` to pick a free one on every start.
+    fixed: Option<u16>,
+    assigned: Option<u16>,
+}
+
+/// An external tool that prints the backend's stacks, see
+/// [`SidecarManager::with_stack_dumper`]. `{pid}` in the arguments is
+/// replaced with the sidecar's PID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackDumper {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl StackDumper {
+    /// `py-spy dump --pid {pid}`, for Python backends.
+    pub fn py_spy() -> Self {
+        Self {
+            program: "py-spy".into(),
+            args: vec!["dump".into(), "--pid".into(), "{pid}".into()],
+        }
+    }
+}
+
+/// How the backend is asked to dump its heap, see
+/// [`SidecarManager::with_backend_dump`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DumpTrigger {
+    /// Send the sidecar this signal number (Unix only).
+    Signal(i32),
+    /// POST to this endpoint, with the auth token if one is configured.
+    Endpoint(String),
+}
+
+/// Returned by the `dump_backend_stack` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct StackDump {
+    /// The tool's stdout followed by its stderr.
+    pub output: String,
+    /// Where the dump was saved, if the log directory is available.
+    pub path: Option<PathBuf>,
+}
+
+/// The settings [`SidecarManager::apply_config`] replaces together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SidecarConfig {
+    pub binary: String,
+    /// Variables set with [`SidecarManager::with_env`].
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub current_dir: Option<PathBuf>,
+    pub health_path: String,
+    pub ready_pattern: Option<String>,
+    pub startup_timeout_ms: u64,
+    /// See [`SidecarManager::with_pre_start_command`].
+    #[serde(default)]
+    pub pre_start_command: Option<Vec<String>>,
+}
+
+impl SidecarConfig {
+    /// Whether going from `self` to `next` needs a new process. The binary,
+    /// its environment and working directory only take effect at spawn;
+    /// the other settings are the manager's own and change in place.
+    pub fn requires_restart(&self, next: &SidecarConfig) -> bool {
+        self.binary != next.binary || self.env != next.env || self.current_dir != next.current_dir
+    }
+}
+
+/// Lock-free view of a manager's port, see
+/// [`SidecarManager::port_reader`]. The port may belong to a backend that
+/// is stopped or still starting.
+#[derive(Debug, Clone)]
+pub struct PortReader {
+    port: Arc<AtomicU16>,
+    restarting: watch::Receiver<bool>,
+}
+
+impl PortReader {
+    pub fn get(&self) -> u16 {
+        self.port.load(Ordering::Acquire)
+    }
+
+    /// Whether the backend is being restarted, so the manager is likely
+    /// locked by the restart.
+    pub fn restarting(&self) -> bool {
+        *self.restarting.borrow()
+    }
+}
+
+/// Result of [`SidecarManager::run_once`].
+#[derive(Debug, Clone, Serialize)]
+pub struct RunOutcome {
+    pub exit: SidecarExit,
+    /// Everything the job printed, as far as the log buffer's capacity
+    /// allows.
+    pub output: Vec<LogEntry>,
+}
+
+/// Pattern for [`PortDiscovery::Pattern`] matching
+/// `Listening on 127.0.0.1:<port>`.
+pub const DEFAULT_PORT_PATTERN: &str = r"Listening on 127\.0\.0\.1:(\d+)";
+
+/// How a backend started with `--port 0` reports the port the OS assigned
+/// it, see [`SidecarManager::with_os_assigned_port`].
+#[derive(Debug, Clone)]
+pub enum PortDiscovery {
+    /// The first output line matching the pattern, whose first capture
+    /// group is the port. Needs [`SidecarStdio::Piped`].
+    Pattern(Regex),
+    /// A file the backend writes the port to, at the path passed in
+    /// `TETHER_PORT_FILE`.
+    File,
+}
+
+/// What the health monitor does once the backend is unhealthy, see
+/// [`SidecarManager::with_on_unhealthy`]. `backend-unhealthy` is emitted
+/// in every case.
+#[derive(Clone)]
+pub enum UnhealthyAction {
+    /// Restart it with the configured [`RestartMode`].
+    Restart,
+    /// Only emit the event, leaving recovery to the app.
+    Notify,
+    /// Call the closure.
+    RunHook(Arc<dyn Fn(&Unhealthy) + Send + Sync>),
+}
+
+impl std::fmt::Debug for UnhealthyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Restart => f.write_str("Restart"),
+            Self::Notify => f.write_str("Notify"),
+            Self::RunHook(_) => f.write_str("RunHook(..)"),
+        }
+    }
+}
+
+/// Cleanup run while no sidecar process is running, see
+/// [`SidecarManager::with_restart_hook`].
+pub type RestartHook = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+/// Payload of the `backend-unhealthy` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct Unhealthy {
+    pub port: u16,
+    pub failed_probes: u32,
+}
+
+/// How [`SidecarManager::with_max_runtime`] measures runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuntimePolicy {
+    /// Time since the first successful start, whether or not the backend
+    /// was running all along.
+    #[default]
+    WallClock,
+    /// Time the backend actually spent running, summed across restarts,
+    /// measured at health monitor granularity.
+    Cumulative,
+}
+
+/// Payload of the `backend-runtime-expired` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeExpired {
+    pub max_runtime_secs: u64,
+}
+
+/// Why the sidecar is being stopped. Sent to the backend's
+/// [HTTP shutdown](ShutdownStep::HttpShutdown) endpoint and included in
+/// [`LifecycleEvent::Stopped`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownReason {
+    /// `stop` or `stop_async` was called, e.g. by the `stop_backend` command.
+    Requested,
+    /// The app is quitting.
+    AppExit,
+    /// It is being restarted on request.
+    Restart,
+    /// It is being restarted to apply a new configuration.
+    ConfigChange,
+    /// It is being replaced by a different binary.
+    BinarySwitch,
+    /// It is being restarted after failing health probes.
+    Unhealthy,
+    /// It is being restarted for exceeding its memory limit.
+    MemoryLimit,
+    /// The idle timer stopped it.
+    Idle,
+    /// It reached its maximum runtime.
+    RuntimeExpired,
+    /// The app received a signal.
+    Signal,
+}
+
+impl ShutdownReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Requested => "requested",
+            Self::AppExit => "app_exit",
+            Self::Restart => "restart",
+            Self::ConfigChange => "config_change",
+            Self::BinarySwitch => "binary_switch",
+            Self::Unhealthy => "unhealthy",
+            Self::MemoryLimit => "memory_limit",
+            Self::Idle => "idle",
+            Self::RuntimeExpired => "runtime_expired",
+            Self::Signal => "signal",
+        }
+    }
+}
+
+impl std::fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Lifecycle changes broadcast to [`SidecarManager::subscribe`]rs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// A process was spawned; it may not be ready yet.
+    Started,
+    /// The sidecar was stopped by us, for the given reason.
+    Stopped(ShutdownReason),
+    /// A restart began, requested or after a crash.
+    Restarting,
+    /// A freshly spawned process is ready to serve on the given port.
+    Ready(u16),
+    /// The process crashed or never became ready, with the reason.
+    Failed(String),
+    /// A server that isn't ours answered on the given port.
+    Compromised(u16),
+    /// Maintenance mode was turned on, or off with the status it left the
+    /// backend in.
+    Maintenance(Option<SidecarStatus>),
+}
+
+/// One entry of `get_status_history`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusTransition {
+    /// Milliseconds since the Unix epoch.
+    pub ts: u64,
+    pub from: SidecarStatus,
+    pub to: SidecarStatus,
+    pub reason: String,
+}
+
+/// Recent status transitions, oldest first.
+struct StatusHistory {
+    entries: VecDeque<StatusTransition>,
+    capacity: usize,
+}
+
+/// Broadcasts [`LifecycleEvent`]s and records the status transition each
+/// one implies, so every place that reports a change also records it.
+#[derive(Clone)]
+struct Lifecycle {
+    tx: broadcast::Sender<LifecycleEvent>,
+    history: Arc<StdMutex<StatusHistory>>,
+}
+
+impl Lifecycle {
+    fn new() -> Self {
+        Self {
+            tx: broadcast::Sender::new(LIFECYCLE_CHANNEL_CAPACITY),
+            history: Arc::new(StdMutex::new(StatusHistory {
+                entries: VecDeque::new(),
+                capacity: DEFAULT_STATUS_HISTORY,
+            })),
+        }
+    }
+
+    fn send(
+        &self,
+        event: LifecycleEvent,
+    ) -> Result<usize, broadcast::error::SendError<LifecycleEvent>> {
+        let (to, reason) = match &event {
+            LifecycleEvent::Started => (SidecarStatus::Running, "process spawned".to_string()),
+            LifecycleEvent::Stopped(reason) => {
+                (SidecarStatus::Stopped, format!("stopped ({})", reason))
+            }
+            LifecycleEvent::Restarting => (SidecarStatus::Restarting, "restart began".to_string()),
+            LifecycleEvent::Ready(port) => {
+                (SidecarStatus::Running, format!("ready on port {}", port))
+            }
+            LifecycleEvent::Failed(reason) => (SidecarStatus::Stopped, reason.clone()),
+            LifecycleEvent::Compromised(port) => (
+                SidecarStatus::Compromised,
+                format!("another server answered on port {}", port),
+            ),
+            LifecycleEvent::Maintenance(None) => (
+                SidecarStatus::Maintenance,
+                "maintenance mode on".to_string(),
+            ),
+            LifecycleEvent::Maintenance(Some(status)) => {
+                (*status, "maintenance mode off".to_string())
+            }
+        };
+        {
+            let mut history = lock(&self.history);
+            let from = history
+                .entries
+                .back()
+                .map_or(SidecarStatus::Stopped, |last| last.to);
+            if history.entries.len() >= history.capacity {
+                history.entries.pop_front();
+            }
+            if history.capacity > 0 {
+                history.entries.push_back(StatusTransition {
+                    ts: now_ms(),
+                    from,
+                    to,
+                    reason,
+                });
+            }
+        }
+        self.tx.send(event)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.tx.subscribe()
+    }
+}
+
+/// Payload of the `backend-identity-mismatch` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentityMismatch {
+    pub port: u16,
+}
+
+/// Payload of the `backend-connecting` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendConnecting {
+    pub port: u16,
+}
+
+/// Payload of the `backend-startup-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupProgress {
+    /// Rough estimate from the time elapsed against the typical startup
+    /// time of earlier launches. Stays below 100 until the backend is
+    /// ready, then is sent once as 100.
+    pub percent: u8,
+}
+
+/// Payload of the `token-rotated` event. The token itself isn't included;
+/// fetch the new one with the `get_api_token` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenRotated {
+    /// Milliseconds since the Unix epoch.
+    pub rotated_at_ms: u64,
+    /// How long the backend keeps accepting the previous token.
+    pub overlap_ms: u64,
+}
+
+/// Payload of the `backend-port-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct PortChanged {
+    pub old_port: u16,
+    pub port: u16,
+}
+
+/// Handle to a spawned sidecar, depending on its [`SidecarStdio`].
+enum SidecarChild {
+    Piped(CommandChild),
+    /// Owned by the thread waiting for it to exit, so only the pid is kept.
+    Inherited(u32),
+}
+
+impl SidecarChild {
+    fn pid(&self) -> u32 {
+        match self {
+            Self::Piped(child) => child.pid(),
+            Self::Inherited(pid) => *pid,
+        }
+    }
+}
+
+/// A spawned sidecar and a way to find out when it has exited.
+struct SidecarProcess {
+    child: SidecarChild,
+    exited: watch::Receiver<bool>,
+    /// Set before we kill the process so its exit isn't counted as a crash.
+    stopping: Arc<AtomicBool>,
+    /// Set while `wait` expects the process to finish on its own.
+    awaited: Arc<AtomicBool>,
+    /// Set once an output line contains the ready pattern, if one is
+    /// configured.
+    ready: Option<watch::Receiver<bool>>,
+    /// The port, once known; only unknown while waiting for an
+    /// OS-assigned one.
+    port: watch::Receiver<Option<u16>>,
+    /// Keeps `port` open for as long as the process is tracked.
+    _port_tx: Arc<watch::Sender<Option<u16>>>,
+    started_at: Instant,
+    /// `started_at` in Unix milliseconds, to match log timestamps.
+    started_ms: u64,
+    /// Removes the cgroup once the process is dropped.
+    _cgroup: Option<Cgroup>,
+    /// Passed as `TETHER_INSTANCE_ID` with an identity check configured.
+    instance_id: Option<String>,
+    /// Set once something else answered on its port.
+    compromised: bool,
+}
+
+impl SidecarProcess {
+    fn has_exited(&self) -> bool {
+        // A dropped sender means the output task is gone, even if it
+        // panicked before reporting the exit.
+        *self.exited.borrow() || self.exited.has_changed().is_err()
+    }
+
+    /// Wait up to `timeout` for the process to exit. Returns whether it did.
+    async fn wait_for_exit(&mut self, timeout: Duration) -> bool {
+        // The sender is dropped once the output task sees the channel close,
+        // which also means the process is gone, so an error counts as exited.
+        tokio::time::timeout(timeout, self.exited.wait_for(|exited| *exited))
+            .await
+            .is_ok()
+    }
+}
+
+/// How to tell that a freshly spawned sidecar is ready to serve.
+pub(crate) struct ReadyCheck {
+    port: watch::Receiver<Option<u16>>,
+    /// Set for [`ReadinessCheck::None`].
+    fixed_delay: Option<Duration>,
+    probes: usize,
+    expected_unavailable: Option<Duration>,
+    ready: Option<watch::Receiver<bool>>,
+    counters: Arc<Counters>,
+    /// When the process was spawned, for
+    /// [`SidecarManager::startup_failure`].
+    pub(crate) since_ms: u64,
+}
+
+impl ReadyCheck {
+    /// Wait up to `timeout` for the ready pattern or, without one, for the
+    /// port to accept connections. Returns the port it is ready on.
+    pub(crate) async fn wait(mut self, timeout: Duration) -> Result<u16, String> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let port = assigned_port(&mut self.port, timeout).await?;
+        let timeout = deadline.saturating_duration_since(tokio::time::Instant::now());
+        if let Some(delay) = self.fixed_delay {
+            tokio::time::sleep(delay.min(timeout)).await;
+            return Ok(port);
+        }
+        let Some(ready) = &mut self.ready else {
+            let quiet = self.expected_unavailable.unwrap_or(timeout / 10);
+            return wait_until_listening(port, timeout, quiet, self.probes, &self.counters)
+                .await
+                .map(|()| port);
+        };
+        match tokio::time::timeout(timeout, ready.wait_for(|ready| *ready)).await {
+            Ok(Ok(_)) => Ok(port),
+            Ok(Err(_)) => Err("exited before it was ready".into()),
+            Err(_) => Err(format!("no ready line after {:?}", timeout)),
+        }
+    }
+}
 
 /// Manages the Python sidecar process.
 pub struct SidecarManager {
-    child: Option<CommandChild>,
-    port: u16,
+    app: Option<AppHandle>,
+    storage: Option<Storage>,
+    backend_log: Option<BackendLogFile>,
+    backend_log_path: Option<PathBuf>,
+    name: String,
+    binary: String,
+    fallback_binary: Option<String>,
+    /// Whether the current process runs `fallback_binary`.
+    using_fallback: bool,
+    env_defaults: HashMap<String, String>,
+    env: HashMap<String, String>,
+    required_env: Vec<String>,
+    current_dir: Option<PathBuf>,
+    pre_start_command: Option<Vec<String>>,
+    search_path: Option<SearchPath>,
+    env_inheritance: EnvInheritance,
+    manual_start: bool,
+    single_instance: Option<SingleInstance>,
+    /// Held while this manager owns the sidecar under `single_instance`.
+    instance_lock: Option<std::fs::File>,
+    /// Exit of the process [`stop`](Self::stop) killed last, which may not
+    /// have been reaped yet.
+    reaping: Option<watch::Receiver<bool>>,
+    umask: Option<u32>,
+    stack_dumper: Option<StackDumper>,
+    /// How to trigger a heap dump, and the file the backend writes it to.
+    backend_dump: Option<(DumpTrigger, PathBuf)>,
+    auth_token: Option<String>,
+    /// Endpoint that takes a new token, and how long the old one stays valid.
+    token_rotation: Option<(String, Duration)>,
+    /// Whether health and version probes send `auth_token` too.
+    authenticated_probes: bool,
+    startup_handshake: Option<Vec<u8>>,
+    child: Option<SidecarProcess>,
+    /// Shared with [`PortReader`]s; only `restart_overlapping` changes it.
+    port: Arc<AtomicU16>,
+    named_ports: Vec<NamedPort>,
+    ownership: Ownership,
+    restart_mode: RestartMode,
+    restart_grace: Duration,
+    startup_timeout: Duration,
+    run_mode: RunMode,
+    stdio: SidecarStdio,
+    port_discovery: Option<PortDiscovery>,
+    spawn_attempts: u32,
+    spawn_retry_delay: Duration,
+    shutdown_steps: Vec<ShutdownStep>,
+    /// Extra tries for a failed HTTP shutdown request, and the delay
+    /// between them.
+    shutdown_retries: (u32, Duration),
+    health_path: String,
+    /// Statuses the health endpoint may answer with; empty for any 2xx.
+    health_statuses: Vec<u16>,
+    ready_pattern: Option<String>,
+    error_pattern: Regex,
+    exit_messages: HashMap<i32, String>,
+    version_path: String,
+    reload_path: Option<String>,
+    log_level_path: String,
+    /// Level requested with `set_log_level`, re-applied to every new
+    /// instance.
+    log_level: Option<String>,
+    config_watch: Option<ConfigWatch>,
+    /// What [`from_env`](Self::from_env) read, which presets don't replace.
+    env_overrides: EnvOverrides,
+    metrics_port: Option<u16>,
+    backend_version: Arc<StdMutex<Option<String>>>,
+    /// Method and path of the request sent once an instance is ready.
+    warmup: Option<(String, String)>,
+    warmup_latency_ms: Arc<StdMutex<Option<u64>>>,
+    forward_signals: bool,
+    signal_actions: Vec<(AppSignal, SignalAction)>,
+    strict: bool,
+    events: EventNames,
+    /// Label of the only window events are sent to, if not all.
+    event_target: Option<String>,
+    restart_cooldown: Duration,
+    last_user_restart: Option<Instant>,
+    monitor_interval: Duration,
+    max_rss_bytes: Option<u64>,
+    cgroup: Option<(PathBuf, CgroupLimits)>,
+    on_unhealthy: Option<UnhealthyAction>,
+    /// Run by `restart` between stopping the old process and starting the
+    /// new one.
+    on_restart_between: Option<RestartHook>,
+    /// Set while an overlapping restart waits for its replacement.
+    overlap_pending: bool,
+    failed_probes: u32,
+    /// Response header that has to echo the instance id.
+    identity_header: Option<String>,
+    /// Whether an external backend answered the last monitor tick.
+    external_reachable: bool,
+    idle_timeout: Option<Duration>,
+    max_runtime: Option<(Duration, RuntimePolicy)>,
+    /// When the first successful `start` happened.
+    first_started: Option<Instant>,
+    /// Running time summed by monitor ticks, for [`RuntimePolicy::Cumulative`].
+    runtime_used: Duration,
+    last_runtime_tick: Option<Instant>,
+    runtime_expired: bool,
+    readiness_probes: usize,
+    readiness_check: ReadinessCheck,
+    expected_unavailable: Option<Duration>,
+    last_activity: Instant,
+    /// Set when the idle timer stopped the sidecar, so the next call wakes it.
+    idle_stopped: bool,
+    /// Set by `stop`/`stop_async` and cleared by `start`, so crash recovery
+    /// never brings back a sidecar that was stopped on purpose.
+    intentionally_stopped: bool,
+    logs: Arc<StdMutex<LogBuffer>>,
+    log_filter: LogFilter,
+    log_levels: LogLevels,
+    restart_count: u32,
+    crash_count: Arc<AtomicU32>,
+    counters: Arc<Counters>,
+    /// Woken by the output task whenever the sidecar crashes.
+    crashed: Arc<Notify>,
+    /// `true` from the start of a restart until the new instance is ready.
+    restarting: watch::Sender<bool>,
+    lifecycle: Lifecycle,
+    log_lines: broadcast::Sender<LogEntry>,
+    request_queue: usize,
+    request_queue_timeout: Duration,
+    proxy_max_response_bytes: u64,
+    ping_path: Option<String>,
+    queued_requests: Arc<AtomicUsize>,
+    /// Set by a [`Drain`] and cleared by `start`.
+    draining: Arc<AtomicBool>,
+    in_flight_requests: watch::Sender<usize>,
+    auto_restart: Option<AutoRestart>,
+    /// Cleared by `set_auto_restart(false)`, which keeps the policy.
+    auto_restart_enabled: bool,
+    /// Leave a crashed sidecar stopped until the next [`wake`](SidecarManager::wake).
+    restart_on_demand: bool,
+    /// Set by `set_maintenance_mode`, which pauses everything automatic.
+    maintenance: bool,
+    restart_attempts_used: u32,
+    stability_window: Duration,
+    /// Woken when auto restart is turned off, cutting a backoff short.
+    restart_cancelled: Arc<Notify>,
+    last_exit: Arc<StdMutex<Option<SidecarExit>>>,
+    metrics_sample: Option<MetricsSample>,
+    /// Reused while the binary's path, size and mtime are unchanged.
+    binary_info: Option<BinaryInfo>,
+}
+
+impl SidecarManager {
+    /// Create a new sidecar manager with the specified port.
+    pub fn new(port: u16) -> Self {
+        Self {
+            app: None,
+            storage: None,
+            backend_log: None,
+            backend_log_path: None,
+            name: DEFAULT_NAME.into(),
+            binary: DEFAULT_BINARY.into(),
+            fallback_binary: None,
+            using_fallback: false,
+            env_defaults: PYTHON_ENV_DEFAULTS
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            env: HashMap::new(),
+            required_env: Vec::new(),
+            current_dir: None,
+            pre_start_command: None,
+            search_path: None,
+            env_inheritance: EnvInheritance::default(),
+            manual_start: false,
+            single_instance: None,
+            instance_lock: None,
+            reaping: None,
+            umask: None,
+            stack_dumper: None,
+            backend_dump: None,
+            auth_token: None,
+            token_rotation: None,
+            authenticated_probes: false,
+            startup_handshake: None,
+            child: None,
+            port: Arc::new(AtomicU16::new(port)),
+            named_ports: Vec::new(),
+            ownership: Ownership::Spawned,
+            restart_mode: RestartMode::default(),
+            restart_grace: Duration::ZERO,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
+            run_mode: RunMode::default(),
+            stdio: SidecarStdio::default(),
+            port_discovery: None,
+            spawn_attempts: DEFAULT_SPAWN_ATTEMPTS,
+            spawn_retry_delay: DEFAULT_SPAWN_RETRY_DELAY,
+            shutdown_steps: vec![ShutdownStep::Sigkill],
+            shutdown_retries: (0, Duration::ZERO),
+            health_path: "/health".into(),
+            health_statuses: Vec::new(),
+            ready_pattern: None,
+            error_pattern: Regex::new(DEFAULT_ERROR_PATTERN)
+                .expect("default error pattern is valid"),
+            exit_messages: HashMap::new(),
+            version_path: "/version".into(),
+            reload_path: None,
+            log_level_path: "/log-level".into(),
+            log_level: None,
+            config_watch: None,
+            env_overrides: EnvOverrides::default(),
+            metrics_port: None,
+            backend_version: Arc::new(StdMutex::new(None)),
+            warmup: None,
+            warmup_latency_ms: Arc::new(StdMutex::new(None)),
+            forward_signals: false,
+            signal_actions: Vec::new(),
+            strict: false,
+            events: EventNames::new(DEFAULT_EVENT_NAMESPACE),
+            event_target: None,
+            restart_cooldown: DEFAULT_RESTART_COOLDOWN,
+            last_user_restart: None,
+            monitor_interval: DEFAULT_MONITOR_INTERVAL,
+            max_rss_bytes: None,
+            cgroup: None,
+            on_unhealthy: None,
+            on_restart_between: None,
+            overlap_pending: false,
+            failed_probes: 0,
+            identity_header: None,
+            external_reachable: true,
+            idle_timeout: None,
+            max_runtime: None,
+            first_started: None,
+            runtime_used: Duration::ZERO,
+            last_runtime_tick: None,
+            runtime_expired: false,
+            readiness_probes: 1,
+            readiness_check: ReadinessCheck::default(),
+            expected_unavailable: None,
+            last_activity: Instant::now(),
+            idle_stopped: false,
+            intentionally_stopped: false,
+            logs: Arc::new(StdMutex::new(LogBuffer::new(
+                DEFAULT_LOG_CAPACITY,
+                DEFAULT_LOG_BYTE_BUDGET,
+            ))),
+            log_filter: LogFilter::default(),
+            log_levels: LogLevels::default(),
+            restart_count: 0,
+            crash_count: Arc::new(AtomicU32::new(0)),
+            counters: Arc::new(Counters::default()),
+            crashed: Arc::new(Notify::new()),
+            restarting: watch::Sender::new(false),
+            lifecycle: Lifecycle::new(),
+            log_lines: broadcast::Sender::new(LOG_CHANNEL_CAPACITY),
+            request_queue: DEFAULT_REQUEST_QUEUE,
+            request_queue_timeout: DEFAULT_REQUEST_QUEUE_TIMEOUT,
+            proxy_max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            ping_path: None,
+            queued_requests: Arc::new(AtomicUsize::new(0)),
+            draining: Arc::new(AtomicBool::new(false)),
+            in_flight_requests: watch::Sender::new(0),
+            auto_restart: None,
+            auto_restart_enabled: true,
+            restart_on_demand: false,
+            maintenance: false,
+            restart_attempts_used: 0,
+            stability_window: DEFAULT_STABILITY_WINDOW,
+            restart_cancelled: Arc::new(Notify::new()),
+            last_exit: Arc::new(StdMutex::new(None)),
+            metrics_sample: None,
+            binary_info: None,
+        }
+    }
+
+    /// Create a manager on `default_port`, overridden by these environment
+    /// variables when set:
+    ///
+    /// - `TETHER_PORT`: port to run the backend on
+    /// - `TETHER_BINARY`: sidecar binary name, as for [`with_binary`](Self::with_binary)
+    /// - `TETHER_HEALTH_PATH`: health endpoint, as for [`with_health_path`](Self::with_health_path)
+    /// - `TETHER_STARTUP_TIMEOUT_MS`: as for [`with_startup_timeout`](Self::with_startup_timeout)
+    ///
+    /// Builder calls made afterwards take precedence over the environment,
+    /// but a [preset](Self::with_preset) only supplies defaults and keeps
+    /// these. Fails on a value that doesn't parse, or on a startup timeout
+    /// [`set_startup_timeout`](Self::set_startup_timeout) would reject.
+    pub fn from_env(default_port: u16) -> Result<Self, SidecarError> {
+        let overrides = EnvOverrides::read(|name| std::env::var(name).ok())?;
+        let mut manager = Self::new(overrides.port.unwrap_or(default_port));
+        if let Some(binary) = &overrides.binary {
+            manager.binary = binary.clone();
+        }
+        if let Some(path) = &overrides.health_path {
+            manager.health_path = path.clone();
+        }
+        if let Some(timeout) = overrides.startup_timeout {
+            manager.startup_timeout = timeout;
+        }
+        manager.env_overrides = overrides;
+        Ok(manager)
+    }
+
+    /// Create a manager on `port` with `config` applied and check it up
+    /// front, as [`validate`](Self::validate) does: that the binary
+    /// resolves, the working directory exists and the port is free. Fails
+    /// with every failed check, so configuration errors surface at launch
+    /// rather than at the first `start`.
+    pub async fn new_validated(
+        app: &AppHandle,
+        port: u16,
+        config: SidecarConfig,
+    ) -> Result<Self, SidecarError> {
+        let mut manager = Self::new(port);
+        manager.initialize(app);
+        manager.apply_config(config).await?;
+        let report = manager.validate(app);
+        if !report.ok {
+            let failed: Vec<String> = report
+                .checks
+                .iter()
+                .filter(|check| !check.passed)
+                .map(|check| format!("{}: {}", check.name, check.detail))
+                .collect();
+            return Err(format!("Invalid API server configuration ({})", failed.join("; ")).into());
+        }
+        Ok(manager)
+    }
+
+    /// Create a manager that attaches to a backend already listening on
+    /// `port` instead of spawning the sidecar.
+    pub fn external(port: u16) -> Self {
+        let mut manager = Self::new(port);
+        manager.ownership = Ownership::External;
+        manager
+    }
+
+    /// Name the sidecar (default `api`), e.g. `worker` next to `api`. Log
+    /// entries carry it, console output is prefixed with it and [`manage`]
+    /// registers the sidecar under it.
+    ///
+    /// [`manage`]: crate::app::manage
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// The sidecar's [name](Self::with_name).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the sidecar binary name (default `api`), as listed in
+    /// `bundle.externalBin` without the `binaries/` prefix.
+    pub fn with_binary(mut self, name: impl Into<String>) -> Self {
+        self.binary = name.into();
+        self
+    }
+
+    /// Spawn the sidecar `name` instead when the primary binary fails to
+    /// start: it can't be spawned, exits, or doesn't become ready within the
+    /// [startup timeout](Self::with_startup_timeout). E.g. a minimal backend
+    /// serving a recovery API, so the app stays partly usable.
+    ///
+    /// With a fallback set, `start` waits for the primary to be ready
+    /// before returning. While the fallback runs, status is
+    /// [`SidecarStatus::RunningFallback`]; every `start` (and so every
+    /// restart) tries the primary binary again first.
+    pub fn with_fallback_binary(mut self, name: impl Into<String>) -> Self {
+        self.fallback_binary = Some(name.into());
+        self
+    }
+
+    /// Replace the environment defaults (initially
+    /// [`PYTHON_ENV_DEFAULTS`]). Variables set with
+    /// [`with_env`](Self::with_env) take precedence over them; pass an
+    /// empty list to disable them.
+    pub fn with_env_defaults<K, V>(mut self, defaults: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env_defaults = defaults
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        self
+    }
+
+    /// Set an environment variable for the sidecar.
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Require `name` to be set, either via [`with_env`](Self::with_env) or
+    /// in the app's own environment. Checked by [`validate`](Self::validate).
+    pub fn with_required_env(mut self, name: impl Into<String>) -> Self {
+        self.required_env.push(name.into());
+        self
+    }
+
+    /// Run the sidecar in `dir` instead of the app's working directory.
+    pub fn with_current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Run `command` (program, then arguments) to completion before every
+    /// spawn of the sidecar, e.g. a script that applies migrations or
+    /// generates secrets. It gets the sidecar's environment (env defaults
+    /// and search path included) and working directory, and the startup
+    /// timeout to finish, after which it is killed; a non-zero exit aborts
+    /// the start with the script's output in the error.
+    pub fn with_pre_start_command(mut self, command: Vec<String>) -> Self {
+        self.pre_start_command = Some(command).filter(|command| !command.is_empty());
+        self
+    }
+
+    /// Don't start the sidecar during app setup; the frontend starts it
+    /// with the `start_backend` command once it has what the backend needs
+    /// (e.g. a license key or a chosen workspace).
+    pub fn with_manual_start(mut self) -> Self {
+        self.manual_start = true;
+        self
+    }
+
+    /// Whether setup leaves starting the sidecar to the frontend.
+    pub fn manual_start(&self) -> bool {
+        self.manual_start
+    }
+
+    /// Set an environment variable for the next spawn, e.g. one the
+    /// frontend only learns at runtime.
+    pub fn set_env(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.env.insert(key.into(), value.into());
+    }
+
+    /// Change the working directory used from the next spawn.
+    pub fn set_current_dir(&mut self, dir: impl Into<PathBuf>) -> Result<(), SidecarError> {
+        let dir = dir.into();
+        if !dir.is_dir() {
+            return Err(format!("Working directory {} does not exist", dir.display()).into());
+        }
+        self.current_dir = Some(dir);
+        Ok(())
+    }
+
+    /// Set the sidecar's `PATH`, e.g. to a bundled `bin/` directory its
+    /// tools live in. Applied on every spawn, after the other variables.
+    pub fn with_search_path(mut self, path: SearchPath) -> Self {
+        self.search_path = Some(path);
+        self
+    }
+
+    /// Limit what the sidecar (and the pre-start command) inherits from the
+    /// app's environment, so it sees only what it needs. Windows programs
+    /// often fail without `SystemRoot`, so allowlist it there rather than
+    /// clearing everything.
+    pub fn with_env_inheritance(mut self, inheritance: EnvInheritance) -> Self {
+        self.env_inheritance = inheritance;
+        self
+    }
+
+    /// Send `method path` to every new instance once it is ready, so lazy
+    /// imports or JIT warmup don't slow down the first real request. The
+    /// latency is logged and reported in metrics; a failure is only logged.
+    pub fn with_warmup(mut self, method: impl Into<String>, path: impl Into<String>) -> Self {
+        self.warmup = Some((method.into(), path.into()));
+        self
+    }
+
+    /// Give the backend another port called `name` (e.g. `metrics`), passed
+    /// as `<arg> <port>` (e.g. `--metrics-port`). With `port` set that port
+    /// is checked to be free before every start; without it a free one is
+    /// picked each time, so it may change across restarts.
+    ///
+    /// An [`Overlap`](RestartMode::Overlap) restart runs both instances at
+    /// once, so a fixed named port only works with the default restart
+    /// mode.
+    pub fn with_named_port(
+        mut self,
+        name: impl Into<String>,
+        arg: impl Into<String>,
+        port: Option<u16>,
+    ) -> Self {
+        let name = name.into();
+        self.named_ports.retain(|p| p.name != name);
+        self.named_ports.push(NamedPort {
+            name,
+            arg: arg.into(),
+            fixed: port,
+            assigned: None,
+        });
+        self
+    }
+
+    /// Spawn the sidecar with `mask` as its umask (e.g. `0o077`), so files
+    /// it creates, such as a token cache, aren't readable by other users.
+    /// Unix only; ignored on Windows.
+    pub fn with_umask(mut self, mask: u32) -> Self {
+        self.umask = Some(mask);
+        self
+    }
+
+    /// Tool run by [`dump_stack`](Self::dump_stack) to capture the backend's
+    /// stacks when it hangs, e.g. [`StackDumper::py_spy`]. The tool has to
+    /// be installed separately; on Linux and macOS it usually needs
+    /// permission to attach to the process.
+    pub fn with_stack_dumper(mut self, dumper: StackDumper) -> Self {
+        self.stack_dumper = Some(dumper);
+        self
+    }
+
+    /// Let [`collect_dump`](Self::collect_dump) ask the backend for a heap
+    /// dump with `trigger`, after which the backend writes it to `path`.
+    pub fn with_backend_dump(mut self, trigger: DumpTrigger, path: impl Into<PathBuf>) -> Self {
+        self.backend_dump = Some((trigger, path.into()));
+        self
+    }
+
+    /// Token the backend expects as `Authorization: Bearer <token>`.
+    ///
+    /// It is passed to the sidecar as `TETHER_API_TOKEN` and added to every
+    /// request sent through [`proxy::forward`](crate::proxy::forward), as
+    /// well as to the app's own control requests such as config reloads. A
+    /// frontend calling the backend directly gets it from the
+    /// `get_api_token` command.
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// Let [`rotate_auth_token`](Self::rotate_auth_token) replace the token
+    /// of a running backend by POSTing `{"token": ..., "overlap_ms": ...}`
+    /// to `path`, authorized with the current token. The backend should
+    /// keep accepting the old token for `overlap`, so requests already in
+    /// flight with it aren't rejected.
+    pub fn with_token_rotation(mut self, path: impl Into<String>, overlap: Duration) -> Self {
+        self.token_rotation = Some((path.into(), overlap));
+        self
+    }
+
+    /// Send the [auth token](Self::with_auth_token) with health and version
+    /// probes too, for backends that protect those endpoints. Off by
+    /// default, since health endpoints are usually left open on purpose.
+    pub fn with_authenticated_probes(mut self, enabled: bool) -> Self {
+        self.authenticated_probes = enabled;
+        self
+    }
+
+    /// `Authorization` header value for control requests, if a token is
+    /// configured.
+    fn authorization(&self) -> Option<String> {
+        self.auth_token
+            .as_ref()
+            .map(|token| format!("Bearer {}", token))
+    }
+
+    /// `Authorization` header value for probes, if they are authenticated.
+    fn probe_authorization(&self) -> Option<String> {
+        self.authorization().filter(|_| self.authenticated_probes)
+    }
+
+    /// Have the backend write its own log to `backend.log` in the app log
+    /// directory, so it ends up next to ours in diagnostics.
+    ///
+    /// Skipped with a warning if the log directory can't be created.
+    pub fn with_backend_log_file(mut self, arg: BackendLogFile) -> Self {
+        self.backend_log = Some(arg);
+        self
+    }
+
+    /// Bytes written to the sidecar's stdin right after every spawn, before
+    /// waiting for it to become ready, for backends that negotiate over
+    /// stdin before serving.
+    ///
+    /// Stdin stays open afterwards for [`write_stdin`](Self::write_stdin);
+    /// the shell plugin only closes it when the process exits.
+    pub fn with_startup_handshake(mut self, handshake: impl Into<Vec<u8>>) -> Self {
+        self.startup_handshake = Some(handshake.into());
+        self
+    }
+
+    /// Choose how `restart` replaces the running sidecar.
+    pub fn with_restart_mode(mut self, mode: RestartMode) -> Self {
+        self.restart_mode = mode;
+        self
+    }
+
+    /// Make up to `attempts` attempts to spawn the sidecar when the OS
+    /// reports a temporary failure (EAGAIN, or antivirus holding the binary
+    /// on Windows), waiting `delay`, then twice that and so on, in between.
+    /// Defaults to 3 attempts starting at 250ms. Errors such as a missing
+    /// binary fail right away.
+    pub fn with_spawn_retries(mut self, attempts: u32, delay: Duration) -> Self {
+        self.spawn_attempts = attempts.max(1);
+        self.spawn_retry_delay = delay;
+        self
+    }
+
+    /// How long a new instance gets to become ready (default 30s).
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// Wait `grace` between the old process exiting and the new one being
+    /// spawned in a [`RestartMode::StopFirst`] restart, for backends whose
+    /// port or lock files take a moment to be released. Zero by default.
+    pub fn with_restart_grace(mut self, grace: Duration) -> Self {
+        self.restart_grace = grace;
+        self
+    }
+
+    /// Run the sidecar as a server or a one-shot job, see [`RunMode`].
+    pub fn with_run_mode(mut self, mode: RunMode) -> Self {
+        self.run_mode = mode;
+        self
+    }
+
+    /// Start the backend with `--port 0` and learn the port the OS assigned
+    /// it through `discovery`, instead of picking one up front, which
+    /// another process could grab before the backend binds it. `port()`
+    /// follows each new instance.
+    pub fn with_os_assigned_port(mut self, discovery: PortDiscovery) -> Self {
+        self.port_discovery = Some(discovery);
+        self
+    }
+
+    /// Connect the sidecar's stdio as described by [`SidecarStdio`].
+    pub fn with_stdio(mut self, stdio: SidecarStdio) -> Self {
+        self.stdio = stdio;
+        self
+    }
+
+    /// Set the escalation sequence used by [`stop_async`](Self::stop_async),
+    /// e.g. `[HttpShutdown, Sigterm { grace: 3s }, Sigkill]`.
+    ///
+    /// The default is a plain `[Sigkill]`, matching [`stop`](Self::stop).
+    pub fn with_shutdown_steps(mut self, steps: Vec<ShutdownStep>) -> Self {
+        self.shutdown_steps = steps;
+        self
+    }
+
+    /// Retry a failed [`ShutdownStep::HttpShutdown`] request up to `retries`
+    /// times, `delay` apart, before moving on to the next step, so a
+    /// backend that is briefly unresponsive isn't killed needlessly. No
+    /// retries by default.
+    pub fn with_shutdown_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.shutdown_retries = (retries, delay);
+        self
+    }
+
+    /// Set the endpoint used to probe the backend's health.
+    pub fn with_health_path(mut self, path: impl Into<String>) -> Self {
+        self.health_path = path.into();
+        self
+    }
+
+    /// Count only these statuses from the health endpoint as healthy
+    /// (default any 2xx), e.g. `[200, 204, 302]` for a backend that
+    /// redirects its health check.
+    pub fn with_health_statuses(mut self, statuses: impl IntoIterator<Item = u16>) -> Self {
+        self.health_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Turn off network probing with [`ReadinessCheck::None`], for backends
+    /// with nothing to probe or where security scanners flag localhost
+    /// requests. `start` then can't tell a backend that never comes up
+    /// from one that is ready, and hangs or deadlocks go unnoticed; only
+    /// crashes are still detected.
+    pub fn with_readiness_check(mut self, check: ReadinessCheck) -> Self {
+        self.readiness_check = check;
+        self
+    }
+
+    /// Treat a new instance as ready once a line of its output (stdout or
+    /// stderr) contains `pattern`, rather than once its port accepts
+    /// connections.
+    pub fn with_ready_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.ready_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Pattern for the stderr line reported as the reason a start failed
+    /// (default `Error|Exception|Traceback`).
+    pub fn with_error_pattern(mut self, pattern: Regex) -> Self {
+        self.error_pattern = pattern;
+        self
+    }
+
+    /// Explain exit code `code` to users with `message`, e.g. "Another
+    /// program is using the port" for a backend that exits with 3 when it
+    /// can't bind. Crashes with the code carry the message in their log
+    /// line, `Failed` lifecycle event and startup error; other codes get the
+    /// generic description.
+    pub fn with_exit_message(mut self, code: i32, message: impl Into<String>) -> Self {
+        self.exit_messages.insert(code, message.into());
+        self
+    }
+
+    /// Apply the defaults of a known backend stack. Call it first so later
+    /// builder calls can override individual settings. Settings
+    /// [`from_env`](Self::from_env) took from the environment are kept.
+    pub fn with_preset(mut self, preset: SidecarPreset) -> Self {
+        let health_path = preset
+            .health_path()
+            .filter(|_| self.env_overrides.health_path.is_none());
+        if let Some(path) = health_path {
+            self.health_path = path.into();
+        }
+        if let Some(defaults) = preset.env_defaults() {
+            self = self.with_env_defaults(defaults.iter().copied());
+        }
+        if let Some(pattern) = preset.ready_pattern() {
+            self.ready_pattern = pattern.map(str::to_string);
+        }
+        self
+    }
+
+    /// Set the endpoint queried for the backend's version after startup.
+    ///
+    /// It may return JSON with a `version` field or the version as plain text.
+    pub fn with_version_path(mut self, path: impl Into<String>) -> Self {
+        self.version_path = path.into();
+        self
+    }
+
+    /// Set the endpoint [`reload_config`](Self::reload_config) POSTs to,
+    /// with the [auth token](Self::with_auth_token) if one is set. Without
+    /// one, it falls back to SIGHUP.
+    pub fn with_reload_path(mut self, path: impl Into<String>) -> Self {
+        self.reload_path = Some(path.into());
+        self
+    }
+
+    /// Set the endpoint [`set_log_level`](Self::set_log_level) POSTs
+    /// `{"level": ...}` to (default `/log-level`), with the
+    /// [auth token](Self::with_auth_token) if one is set.
+    pub fn with_log_level_path(mut self, path: impl Into<String>) -> Self {
+        self.log_level_path = path.into();
+        self
+    }
+
+    /// Restart or reload the backend when `path` (a file, or any file below
+    /// a directory) changes, once changes have stopped for 500ms.
+    pub fn with_config_watch(mut self, path: impl Into<PathBuf>, action: WatchAction) -> Self {
+        self.config_watch = Some(ConfigWatch {
+            path: path.into(),
+            action,
+            debounce: DEFAULT_DEBOUNCE,
+        });
+        self
+    }
+
+    /// Quiet period before acting on config changes (default 500ms).
+    /// Only meaningful together with
+    /// [`with_config_watch`](Self::with_config_watch).
+    pub fn with_config_watch_debounce(mut self, debounce: Duration) -> Self {
+        if let Some(watch) = &mut self.config_watch {
+            watch.debounce = debounce;
+        }
+        self
+    }
+
+    /// The config watch, if one is configured.
+    pub fn config_watch(&self) -> Option<&ConfigWatch> {
+        self.config_watch.as_ref()
+    }
+
+    /// Serve the manager's [`metrics`](Self::metrics) in the Prometheus
+    /// text format at `http://127.0.0.1:<port>/metrics`, started by
+    /// [`app::manage`](crate::app::manage). These are tether's own numbers
+    /// about managing the backend, not the backend's metrics. Off by
+    /// default.
+    pub fn with_metrics_port(mut self, port: u16) -> Self {
+        self.metrics_port = Some(port);
+        self
+    }
+
+    /// Port of the metrics endpoint, if one is configured.
+    pub fn metrics_port(&self) -> Option<u16> {
+        self.metrics_port
+    }
+
+    /// Stop the sidecar gracefully when the app receives SIGTERM/SIGINT, or
+    /// do what [`with_signal_action`](Self::with_signal_action) says.
+    ///
+    /// See [`signals`](crate::signals) for platform limitations.
+    pub fn with_signal_forwarding(mut self, enabled: bool) -> Self {
+        self.forward_signals = enabled;
+        self
+    }
+
+    /// What to do with this sidecar when the app receives `signal`
+    /// (default [`SignalAction::Stop`] for both), so sidecars in a
+    /// [registry](crate::registry::SidecarRegistry) can shut down
+    /// differently. Turns on signal forwarding.
+    pub fn with_signal_action(mut self, signal: AppSignal, action: SignalAction) -> Self {
+        self.forward_signals = true;
+        self.signal_actions.retain(|(s, _)| *s != signal);
+        self.signal_actions.push((signal, action));
+        self
+    }
+
+    /// Whether termination signals should be forwarded to the sidecar.
+    pub fn forwards_signals(&self) -> bool {
+        self.forward_signals
+    }
+
+    /// What this sidecar does when the app receives `signal`.
+    pub fn signal_action(&self, signal: AppSignal) -> SignalAction {
+        self.signal_actions
+            .iter()
+            .find(|(s, _)| *s == signal)
+            .map(|(_, action)| *action)
+            .unwrap_or_default()
+    }
+
+    /// Apply the [`signal_action`](Self::signal_action) for `signal`. Like
+    /// a stop, the sidecar isn't restarted after it exits.
+    pub async fn handle_app_signal(&mut self, signal: AppSignal) -> Result<(), SidecarError> {
+        match self.signal_action(signal) {
+            SignalAction::Stop => self.stop_for(ShutdownReason::Signal).await.map(|_| ()),
+            SignalAction::Send(child_signal) => {
+                self.send_signal(child_signal).await;
+                Ok(())
+            }
+            SignalAction::Ignore => Ok(()),
+        }
+    }
+
+    /// Send `signal` to the sidecar and wait a few seconds for it to exit.
+    async fn send_signal(&mut self, signal: ChildSignal) {
+        if self.ownership == Ownership::External {
+            return;
+        }
+        self.intentionally_stopped = true;
+        let Some(mut process) = self.take_running() else {
+            return;
+        };
+        let pid = process.child.pid();
+        process.stopping.store(true, Ordering::Relaxed);
+        info!("Sending {:?} to API server...", signal);
+        let delivered = match signal {
+            ChildSignal::Terminate => {
+                terminate_sidecar(pid);
+                true
+            }
+            ChildSignal::Interrupt => process::interrupt(pid),
+            ChildSignal::Hangup => hangup_sidecar(pid),
+        };
+        if !delivered {
+            terminate_sidecar(pid);
+        }
+        if process.wait_for_exit(FORWARDED_SIGNAL_GRACE).await {
+            let _ = self
+                .lifecycle
+                .send(LifecycleEvent::Stopped(ShutdownReason::Signal));
+            info!("API server stopped");
+        } else {
+            warn!(
+                "API server did not exit within {:?} of {:?}",
+                FORWARDED_SIGNAL_GRACE, signal
+            );
+            // Keep it tracked so a later stop still kills it
+            self.child = Some(process);
+        }
+    }
+
+    /// In strict mode `start` fails with [`SidecarError::AlreadyRunning`]
+    /// when the sidecar is already running, instead of returning `Ok`. This
+    /// catches accidental double starts; the default is lenient.
+    pub fn with_strict_mode(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Take an exclusive lock on `<binary>.lock` in the app data directory
+    /// while starting, so two copies of the app launched together don't
+    /// both spawn the sidecar and clobber each other's files. `policy`
+    /// decides what the second copy does. The lock is held until the
+    /// sidecar is stopped; off by default.
+    pub fn with_single_instance(mut self, policy: SingleInstance) -> Self {
+        self.single_instance = Some(policy);
+        self
+    }
+
+    /// Prefix every emitted event name with `namespace` (default
+    /// [`DEFAULT_EVENT_NAMESPACE`]), so several instances or the app's own
+    /// events don't collide.
+    pub fn with_event_namespace(mut self, namespace: &str) -> Self {
+        self.events = EventNames::new(namespace);
+        self
+    }
+
+    /// Send this manager's events only to the window labelled `label`, e.g.
+    /// a dedicated console window, instead of to every window. Other
+    /// windows then never see the backend's output.
+    pub fn with_event_target(mut self, label: impl Into<String>) -> Self {
+        self.event_target = Some(label.into());
+        self
+    }
+
+    /// The resolved names of the events this manager emits.
+    pub fn event_names(&self) -> &EventNames {
+        &self.events
+    }
+
+    /// Minimum time between user-initiated restarts (default 2s).
+    ///
+    /// Only [`user_restart`](Self::user_restart) is rate-limited; calling
+    /// [`restart`](Self::restart) directly is not.
+    pub fn with_restart_cooldown(mut self, cooldown: Duration) -> Self {
+        self.restart_cooldown = cooldown;
+        self
+    }
+
+    /// Time between health monitor ticks (default 5s).
+    pub fn with_monitor_interval(mut self, interval: Duration) -> Self {
+        self.monitor_interval = interval;
+        self
+    }
+
+    /// Place each spawned sidecar in the cgroup v2 at `path` (e.g. a
+    /// directory under a delegated `/sys/fs/cgroup/...` slice) with
+    /// `limits`, creating it if it doesn't exist. A cgroup we created is
+    /// removed once its process has exited.
+    ///
+    /// Linux only, and best effort: without cgroups v2 or permission to
+    /// write there, a warning is logged and the sidecar runs unconfined.
+    /// The process is moved in right after it is spawned, so anything it
+    /// starts in its first moments may escape the limits.
+    pub fn with_cgroup(mut self, path: impl Into<PathBuf>, limits: CgroupLimits) -> Self {
+        self.cgroup = Some((path.into(), limits));
+        self
+    }
+
+    /// Gracefully restart the backend when its resident memory exceeds
+    /// `bytes`, checked on every health monitor tick.
+    pub fn with_max_rss_bytes(mut self, bytes: u64) -> Self {
+        self.max_rss_bytes = Some(bytes);
+        self
+    }
+
+    /// Probe the health endpoint on every health monitor tick and, after
+    /// three failures in a row, emit `backend-unhealthy` and take `action`.
+    /// Probing starts once an instance has had its startup timeout to come
+    /// up. Off by default.
+    pub fn with_on_unhealthy(mut self, action: UnhealthyAction) -> Self {
+        self.on_unhealthy = Some(action);
+        self
+    }
+
+    /// Call `hook` during [`restart`](Self::restart) once the old process
+    /// has exited and before the new one is spawned, e.g. to remove a stale
+    /// socket. An error aborts the restart, leaving the sidecar stopped,
+    /// and is returned from `restart`.
+    ///
+    /// Only runs with [`RestartMode::StopFirst`]; an overlapping restart is
+    /// never without a running process.
+    pub fn with_restart_hook(
+        mut self,
+        hook: impl Fn() -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.on_restart_between = Some(Arc::new(hook));
+        self
+    }
+
+    /// Stop the sidecar after `timeout` without
+    /// [`mark_activity`](Self::mark_activity), checked on every health
+    /// monitor tick. It is started again by the next [`wake`](Self::wake).
+    ///
+    /// Off by default.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Stop the sidecar for good once it has run for `max_runtime`, as
+    /// measured by `policy`, and emit `backend-runtime-expired`, e.g. for
+    /// trial or kiosk builds. The clock starts at the first successful
+    /// `start` and keeps counting across restarts; later starts fail.
+    ///
+    /// Checked on every health monitor tick. Off by default.
+    pub fn with_max_runtime(mut self, max_runtime: Duration, policy: RuntimePolicy) -> Self {
+        self.max_runtime = Some((max_runtime, policy));
+        self
+    }
+
+    /// Number of connection attempts kept in flight while waiting for a new
+    /// instance to become ready (default 1). The first one to connect wins,
+    /// which helps backends that are slow to accept one connection but fast
+    /// on another.
+    pub fn with_readiness_probes(mut self, probes: usize) -> Self {
+        self.readiness_probes = probes.max(1);
+        self
+    }
+
+    /// How long after spawning refused connections are expected while the
+    /// backend boots (default a tenth of the startup timeout). Failed
+    /// readiness probes in this window are only logged at trace level; a
+    /// warning is logged once the backend is still unreachable after it.
+    pub fn with_expected_unavailable(mut self, window: Duration) -> Self {
+        self.expected_unavailable = Some(window);
+        self
+    }
+
+    /// Restart the sidecar when it crashes, making up to `max_attempts`
+    /// attempts with exponential backoff starting at `initial_delay`. The
+    /// delay doubles without a cap or jitter until changed with
+    /// [`set_restart_backoff`](Self::set_restart_backoff).
+    ///
+    /// Attempts add up across crashes until the backend has stayed up for
+    /// the [stability window](Self::with_stability_window), so a crash loop
+    /// runs out of attempts while rare crashes each start from the first.
+    /// Runs on the health monitor. Off by default.
+    pub fn with_auto_restart(mut self, max_attempts: u32, initial_delay: Duration) -> Self {
+        self.auto_restart = Some(AutoRestart {
+            max_attempts,
+            initial_delay,
+            multiplier: 2,
+            max_delay: None,
+            jitter: Duration::ZERO,
+        });
+        self
+    }
+
+    /// Instead of restarting a crashed sidecar right away, leave it stopped
+    /// and start it again on the next [`wake`](Self::wake), i.e. the next
+    /// `get_api_port`, `proxy_request` or `ping`. Suits bursty backends and
+    /// avoids restart storms for one that keeps crashing while idle.
+    ///
+    /// The first request after a crash waits for the whole startup, up to
+    /// the startup timeout. Takes precedence over
+    /// [`with_auto_restart`](Self::with_auto_restart). Off by default.
+    pub fn with_restart_on_demand(mut self) -> Self {
+        self.restart_on_demand = true;
+        self
+    }
+
+    /// How long the backend has to stay up, without failing health probes,
+    /// for earlier crash restart attempts to be forgotten (default 60s).
+    pub fn with_stability_window(mut self, window: Duration) -> Self {
+        self.stability_window = window;
+        self
+    }
+
+    /// The port registered as `name` with
+    /// [`with_named_port`](Self::with_named_port), once one has been
+    /// assigned by a start.
+    pub fn port_named(&self, name: &str) -> Option<u16> {
+        self.named_ports
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| p.assigned)
+    }
+
+    /// Every assigned named port, by name.
+    pub fn named_ports(&self) -> HashMap<String, u16> {
+        self.named_ports
+            .iter()
+            .filter_map(|p| Some((p.name.clone(), p.assigned?)))
+            .collect()
+    }
+
+    /// Pick or check every named port before a spawn.
+    fn assign_named_ports(&mut self) -> Result<(), SidecarError> {
+        for named in &mut self.named_ports {
+            let port = match named.fixed {
+                Some(port) => {
+                    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).map_err(|e| {
+                        format!("Port {} for {} is not available: {}", port, named.name, e)
+                    })?;
+                    port
+                }
+                None => portpicker::pick_unused_port()
+                    .ok_or_else(|| format!("No available port for {}", named.name))?,
+            };
+            named.assigned = Some(port);
+        }
+        Ok(())
+    }
+
+    /// Number of concurrent readiness probes.
+    pub fn readiness_probes(&self) -> usize {
+        self.readiness_probes
+    }
+
+    /// How long a new instance gets to become ready.
+    pub fn startup_timeout(&self) -> Duration {
+        self.startup_timeout
+    }
+
+    /// Change the startup timeout at runtime, e.g. for a slow machine.
+    /// Applies from the next start; must be between 1s and 10 minutes.
+    pub fn set_startup_timeout(&mut self, timeout: Duration) -> Result<(), SidecarError> {
+        check_startup_timeout(timeout)?;
+        info!("Startup timeout set to {:?}", timeout);
+        self.startup_timeout = timeout;
+        Ok(())
+    }
+
+    /// While the backend restarts, let up to `max_waiting` proxied requests
+    /// wait up to `timeout` for it (default 16 for 10s) instead of failing.
+    pub fn with_request_queue(mut self, max_waiting: usize, timeout: Duration) -> Self {
+        self.request_queue = max_waiting;
+        self.request_queue_timeout = timeout;
+        self
+    }
+
+    /// Largest response `proxy_request` relays (default 50 MB). Bigger ones
+    /// fail with an error rather than being buffered.
+    pub fn with_proxy_max_response(mut self, bytes: u64) -> Self {
+        self.proxy_max_response_bytes = bytes;
+        self
+    }
+
+    /// Response size limit for `proxy_request`.
+    pub fn proxy_max_response_bytes(&self) -> u64 {
+        self.proxy_max_response_bytes
+    }
+
+    /// Cheap endpoint the `ping` command requests through the proxy
+    /// (default the health path).
+    pub fn with_ping_path(mut self, path: impl Into<String>) -> Self {
+        self.ping_path = Some(path.into());
+        self
+    }
+
+    /// Path the `ping` command requests.
+    pub fn ping_path(&self) -> &str {
+        self.ping_path.as_deref().unwrap_or(&self.health_path)
+    }
+
+    /// Gate a proxied request has to pass before it is sent.
+    pub fn request_gate(&self) -> RequestGate {
+        RequestGate {
+            restarting: self.restarting.subscribe(),
+            waiting: self.queued_requests.clone(),
+            max_waiting: self.request_queue,
+            timeout: self.request_queue_timeout,
+            draining: self.draining.clone(),
+            in_flight: self.in_flight_requests.clone(),
+        }
+    }
+
+    /// Handle to drain proxied requests before a shutdown. It doesn't borrow
+    /// the manager, so the wait doesn't block requests that still need it.
+    pub fn drainer(&self) -> Drain {
+        Drain {
+            draining: self.draining.clone(),
+            in_flight: self.in_flight_requests.subscribe(),
+        }
+    }
+
+    /// Receive [`LifecycleEvent`]s from now on, so in-process code (e.g. a
+    /// websocket relay) can reconnect after restarts without going through
+    /// Tauri events.
+    ///
+    /// Sending never waits for subscribers. One that falls more than 16
+    /// events behind gets `RecvError::Lagged` with the number it missed and
+    /// resumes from the oldest event still buffered; only the latest state
+    /// is reliable, so treat a lag as "re-check the manager".
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.lifecycle.subscribe()
+    }
+
+    /// Keep the last `capacity` status transitions for
+    /// [`status_history`](Self::status_history) (default 100).
+    pub fn with_status_history(self, capacity: usize) -> Self {
+        lock(&self.lifecycle.history).capacity = capacity;
+        self
+    }
+
+    /// Status transitions with their reasons, oldest first, for telling
+    /// when and why a flapping backend went down.
+    pub fn status_history(&self) -> Vec<StatusTransition> {
+        lock(&self.lifecycle.history)
+            .entries
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Receive every captured output line from now on, including ones the
+    /// log filter keeps out of the buffer. See [`wait_for_log`] to wait for
+    /// a particular one without holding the manager.
+    pub fn subscribe_logs(&self) -> broadcast::Receiver<LogEntry> {
+        self.log_lines.subscribe()
+    }
+
+    /// Mark the backend as restarting, holding back proxied requests.
+    pub(crate) fn begin_restart(&self) {
+        self.restarting.send_replace(true);
+        let _ = self.lifecycle.send(LifecycleEvent::Restarting);
+    }
+
+    /// Release held requests.
+    pub(crate) fn end_restart(&self) {
+        self.restarting.send_replace(false);
+    }
+
+    /// Release held requests once the current instance is ready, or right
+    /// away if there is none.
+    fn end_restart_when_ready(&self) {
+        let Some(process) = self.child.as_ref().filter(|p| !p.has_exited()) else {
+            self.end_restart();
+            return;
+        };
+        let ready = self.ready_check(process);
+        let restarting = self.restarting.clone();
+        let timeout = self.startup_timeout;
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = ready.wait(timeout).await {
+                warn!("API server did not become ready after restart: {}", e);
+            }
+            restarting.send_replace(false);
+        });
+    }
+
+    /// Readiness of the running instance, or `None` if none is running.
+    pub(crate) fn running_ready_check(&self) -> Option<ReadyCheck> {
+        self.child
+            .as_ref()
+            .filter(|p| !p.has_exited())
+            .map(|process| self.ready_check(process))
+    }
+
+    /// The crash restart policy, if enabled. Always `None` for
+    /// [`RunMode::OneShot`], with
+    /// [`with_restart_on_demand`](Self::with_restart_on_demand) and in
+    /// [maintenance mode](Self::set_maintenance_mode).
+    pub fn auto_restart(&self) -> Option<AutoRestart> {
+        self.auto_restart.filter(|_| {
+            self.run_mode == RunMode::Server
+                && self.auto_restart_enabled
+                && !self.restart_on_demand
+                && !self.maintenance
+        })
+    }
+
+    /// The configured crash restart policy and how much of it the current
+    /// crash has used, or `None` without [`with_auto_restart`](Self::with_auto_restart).
+    pub fn auto_restart_info(&self) -> Option<AutoRestartInfo> {
+        let policy = self.auto_restart?;
+        Some(AutoRestartInfo {
+            enabled: self.auto_restart().is_some(),
+            max_attempts: policy.max_attempts,
+            attempts_used: self.restart_attempts_used,
+            initial_delay_ms: policy.initial_delay.as_millis() as u64,
+            backoff_factor: policy.multiplier,
+            max_delay_ms: policy.max_delay.map(|max| max.as_millis() as u64),
+            jitter_ms: policy.jitter.as_millis() as u64,
+        })
+    }
+
+    /// Replace the crash restart backoff. A restart cycle already under way
+    /// keeps the settings it started with.
+    pub fn set_restart_backoff(&mut self, backoff: RestartBackoff) -> Result<(), SidecarError> {
+        if self.auto_restart.is_none() {
+            return Err("Auto restart is not configured".into());
+        }
+        let initial_delay = Duration::from_millis(backoff.initial_delay_ms);
+        let max_delay = backoff.max_delay_ms.map(Duration::from_millis);
+        let jitter = Duration::from_millis(backoff.jitter_ms);
+        if backoff.max_attempts == 0 {
+            return Err("max_attempts must be at least 1".into());
+        }
+        if !(1..=10).contains(&backoff.multiplier) {
+            return Err("multiplier must be between 1 and 10".into());
+        }
+        if [Some(initial_delay), max_delay, Some(jitter)]
+            .into_iter()
+            .flatten()
+            .any(|delay| delay > MAX_RESTART_DELAY)
+        {
+            return Err(format!("Delays must not exceed {:?}", MAX_RESTART_DELAY).into());
+        }
+        if max_delay.is_some_and(|max| max < initial_delay) {
+            return Err("max_delay_ms must not be less than initial_delay_ms".into());
+        }
+
+        info!("Restart backoff set to {:?}", backoff);
+        self.auto_restart = Some(AutoRestart {
+            max_attempts: backoff.max_attempts,
+            initial_delay,
+            multiplier: backoff.multiplier,
+            max_delay,
+            jitter,
+        });
+        Ok(())
+    }
+
+    /// Turn crash recovery on or off at runtime. Turning it off cancels a
+    /// pending attempt; the crashed sidecar is then left stopped.
+    pub fn set_auto_restart(&mut self, enabled: bool) -> Result<(), SidecarError> {
+        if self.auto_restart.is_none() {
+            return Err("Auto restart is not configured".into());
+        }
+        self.auto_restart_enabled = enabled;
+        if !enabled {
+            info!("Auto restart disabled");
+            self.restart_cancelled.notify_waiters();
+        }
+        Ok(())
+    }
+
+    /// Pause crash restarts and every health monitor action (idle stop,
+    /// memory and health restarts, runtime limit), so a developer can kill
+    /// and poke at the backend without it coming back to life. Shown as
+    /// [`SidecarStatus::Maintenance`] in the status, with `maintenance` set.
+    ///
+    /// Leaving maintenance mode runs a health monitor tick right away and
+    /// recovers the sidecar if it crashed meanwhile.
+    pub async fn set_maintenance_mode(&mut self, enabled: bool) {
+        if enabled == self.maintenance {
+            return;
+        }
+        self.maintenance = enabled;
+        if enabled {
+            info!("Maintenance mode on, pausing automatic restarts");
+            let _ = self.lifecycle.send(LifecycleEvent::Maintenance(None));
+            self.restart_cancelled.notify_waiters();
+            return;
+        }
+        info!("Maintenance mode off");
+        let status = self.status().status;
+        let _ = self
+            .lifecycle
+            .send(LifecycleEvent::Maintenance(Some(status)));
+        self.failed_probes = 0;
+        self.monitor_tick().await;
+        if self.crashed() {
+            self.crashed.notify_one();
+        }
+    }
+
+    /// Crash restart attempts made since the backend was last stable.
+    pub(crate) fn restart_attempts_used(&self) -> u32 {
+        self.restart_attempts_used
+    }
+
+    /// Record the crash restart attempt in progress.
+    pub(crate) fn set_restart_attempts_used(&mut self, attempts: u32) {
+        self.restart_attempts_used = attempts;
+    }
+
+    /// Forget earlier crash restart attempts once the backend has been up
+    /// and passing health probes for the stability window.
+    fn reset_backoff_if_stable(&mut self) {
+        if self.restart_attempts_used == 0 || self.failed_probes > 0 {
+            return;
+        }
+        let stable = self
+            .child
+            .as_ref()
+            .filter(|p| !p.has_exited())
+            .is_some_and(|p| p.started_at.elapsed() >= self.stability_window);
+        if stable {
+            info!(
+                "API server stable for {:?}, resetting crash restart backoff",
+                self.stability_window
+            );
+            self.restart_attempts_used = 0;
+        }
+    }
+
+    /// Notified when auto restart is turned off.
+    pub(crate) fn restart_cancel_notifier(&self) -> Arc<Notify> {
+        self.restart_cancelled.clone()
+    }
+
+    /// Time between health monitor ticks.
+    pub fn monitor_interval(&self) -> Duration {
+        self.monitor_interval
+    }
+
+    /// Whether anything is configured that needs the health monitor. An
+    /// external backend always does, to notice it going away.
+    pub fn monitor_enabled(&self) -> bool {
+        self.run_mode == RunMode::Server
+            && (self.ownership == Ownership::External
+                || self.max_rss_bytes.is_some()
+                || self.idle_timeout.is_some()
+                || self.max_runtime.is_some()
+                || self.on_unhealthy.is_some()
+                || self.identity_header.is_some()
+                || self.auto_restart.is_some())
+    }
+
+    /// Notified whenever the sidecar crashes.
+    pub(crate) fn crash_notifier(&self) -> Arc<Notify> {
+        self.crashed.clone()
+    }
+
+    /// Whether the sidecar died without us stopping it and hasn't been
+    /// started again since.
+    pub(crate) fn crashed(&self) -> bool {
+        self.ownership == Ownership::Spawned
+            && !self.intentionally_stopped
+            && self.child.as_ref().is_some_and(|p| p.has_exited())
+    }
+
+    /// Tell the frontend a crash restart is about to be attempted.
+    pub(crate) fn emit_restart_attempt(&self, attempt: RestartAttempt) {
+        if let Some(app) = &self.app {
+            let _ = emit(
+                app,
+                self.event_target.as_deref(),
+                &self.events.restart_attempt,
+                attempt,
+            );
+        }
+    }
+
+    /// Start the sidecar again after a crash. Returns how to tell when the
+    /// new instance is ready.
+    pub(crate) async fn restart_after_crash(&mut self) -> Result<ReadyCheck, SidecarError> {
+        let app = self.app.clone().ok_or(SidecarError::NoAppHandle)?;
+        self.restart_count += 1;
+        self.counters.crash_restarts.fetch_add(1, Ordering::Relaxed);
+        self.start(&app).await?;
+        let process = self.child.as_ref().ok_or(SidecarError::NotRunning)?;
+        Ok(self.ready_check(process))
+    }
+
+    /// How to wait for `process` to become ready.
+    fn ready_check(&self, process: &SidecarProcess) -> ReadyCheck {
+        ReadyCheck {
+            port: process.port.clone(),
+            fixed_delay: match self.readiness_check {
+                ReadinessCheck::Probe => None,
+                ReadinessCheck::None { delay } => Some(delay),
+            },
+            probes: self.readiness_probes,
+            expected_unavailable: self.expected_unavailable,
+            ready: process.ready.clone(),
+            counters: self.counters.clone(),
+            since_ms: process.started_ms,
+        }
+    }
+
+    /// Describe a failed start from the stderr captured since `since_ms`.
+    pub(crate) fn startup_failure(&self, message: String, since_ms: u64) -> SidecarError {
+        let (stderr, errors) = {
+            let logs = lock(&self.logs);
+            (logs.stderr_since(since_ms), logs.errors_since(since_ms))
+        };
+        // A registered exit message beats guessing from stderr, as long as
+        // the exit is this process's and not an earlier one's
+        let failure_reason = self
+            .child
+            .as_ref()
+            .filter(|p| p.has_exited())
+            .and_then(|_| self.last_exit())
+            .and_then(|exit| self.exit_message(&exit))
+            .or_else(|| {
+                errors
+                    .iter()
+                    .find(|line| self.error_pattern.is_match(line))
+                    .map(|line| line.trim().to_string())
+            });
+        let skip = stderr.len().saturating_sub(STDERR_TAIL_LINES);
+        SidecarError::StartupFailed {
+            message,
+            failure_reason,
+            stderr_tail: stderr.into_iter().skip(skip).collect(),
+        }
+    }
+
+    /// Record that the backend is in use, resetting the idle timer.
+    pub fn mark_activity(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    /// Mark activity and start the sidecar again if the idle timer stopped
+    /// it, or if it crashed with
+    /// [`with_restart_on_demand`](Self::with_restart_on_demand).
+    pub async fn wake(&mut self) -> Result<(), SidecarError> {
+        self.mark_activity();
+        if self.restart_on_demand
+            && !self.maintenance
+            && self.run_mode == RunMode::Server
+            && self.crashed()
+        {
+            info!("Restarting crashed API server on demand...");
+            let ready = self.restart_after_crash().await?;
+            let since_ms = ready.since_ms;
+            return match ready.wait(self.startup_timeout).await {
+                Ok(_) => Ok(()),
+                Err(e) => Err(self.startup_failure(
+                    format!("Restarted API server did not come up ({})", e),
+                    since_ms,
+                )),
+            };
+        }
+        if !self.idle_stopped {
+            return Ok(());
+        }
+        let app = self.app.clone().ok_or(SidecarError::NoAppHandle)?;
+        info!("Waking idle API server...");
+        self.start(&app).await.map(|_| ())
+    }
+
+    /// Number of output lines kept in memory for `get_recent_logs`
+    /// (default 1000).
+    pub fn with_log_capacity(self, lines: usize) -> Self {
+        lock(&self.logs).set_capacity(lines);
+        self
+    }
+
+    /// Number of `stream` lines kept in memory, within the overall
+    /// [capacity](Self::with_log_capacity) (unlimited by default). E.g.
+    /// keep fewer stdout lines so chatty output doesn't push out the stderr
+    /// that explains a failure.
+    pub fn with_stream_log_capacity(self, stream: LogStream, lines: usize) -> Self {
+        lock(&self.logs).set_stream_capacity(stream, lines);
+        self
+    }
+
+    /// Maximum combined size of the text kept in memory for
+    /// `get_recent_logs` (default 2 MB), on top of the line count.
+    pub fn with_log_byte_budget(self, bytes: usize) -> Self {
+        lock(&self.logs).set_byte_budget(bytes);
+        self
+    }
+
+    /// Only keep and forward output lines matching `pattern` (or another
+    /// include pattern). Filtered lines are still printed to the console
+    /// and still count for the ready pattern.
+    pub fn with_log_include(mut self, pattern: Regex) -> Self {
+        self.log_filter.include(pattern);
+        self
+    }
+
+    /// Drop output lines matching `pattern` before they are kept or
+    /// forwarded, e.g. the backend's access log lines for our own health
+    /// probes (`"GET /health`). Takes precedence over include patterns.
+    pub fn with_log_exclude(mut self, pattern: Regex) -> Self {
+        self.log_filter.exclude(pattern);
+        self
+    }
+
+    /// Give stderr lines matching `pattern` `level`, e.g. uvicorn's
+    /// `"INFO:"` lines [`LogLevel::Info`]. The first matching rule wins.
+    ///
+    /// Only error lines are considered for the reason a start failed.
+    pub fn with_stderr_level(mut self, pattern: Regex, level: LogLevel) -> Self {
+        self.log_levels.stderr_rule(pattern, level);
+        self
+    }
+
+    /// Level of stderr lines that match no
+    /// [`with_stderr_level`](Self::with_stderr_level) rule (default
+    /// [`LogLevel::Error`]).
+    pub fn with_stderr_default_level(mut self, level: LogLevel) -> Self {
+        self.log_levels.set_stderr_default(level);
+        self
+    }
+
+    /// Get the port the sidecar is running on.
+    pub fn port(&self) -> u16 {
+        self.port.load(Ordering::Acquire)
+    }
+
+    /// A handle that reads the current port without locking the manager,
+    /// e.g. while a restart holds the lock.
+    pub fn port_reader(&self) -> PortReader {
+        PortReader {
+            port: self.port.clone(),
+            restarting: self.restarting.subscribe(),
+        }
+    }
+
+    /// The port of the backend, or [`SidecarError::NotRunning`] if it isn't
+    /// running, so callers don't send requests to a dead server.
+    pub fn running_port(&self) -> Result<u16, SidecarError> {
+        match self.status().status {
+            SidecarStatus::Running | SidecarStatus::RunningFallback => Ok(self.port()),
+            SidecarStatus::Maintenance if self.process_up() => Ok(self.port()),
+            SidecarStatus::Stopped | SidecarStatus::Restarting | SidecarStatus::Maintenance => {
+                Err(SidecarError::NotRunning)
+            }
+            SidecarStatus::Compromised => Err(format!(
+                "Another server is answering on port {}, not sending it requests",
+                self.port()
+            )
+            .into()),
+        }
+    }
+
+    /// PID of the running sidecar, or `None` if it isn't running or is
+    /// external. Changes after a restart.
+    pub fn pid(&self) -> Option<u32> {
+        self.child
+            .as_ref()
+            .filter(|p| !p.has_exited())
+            .map(|p| p.child.pid())
+    }
+
+    /// Where disk-writing features put their files, once `start` has
+    /// checked it.
+    pub fn storage(&self) -> Option<&Storage> {
+        self.storage.as_ref()
+    }
+
+    /// Path the backend was told to write its own log to, if any.
+    pub fn backend_log_file(&self) -> Option<&Path> {
+        self.backend_log_path.as_deref()
+    }
+
+    /// A page of the backend's log file, or of its `rotation`th rotated copy
+    /// (`backend.log.1` and so on) when `rotation` is above zero. See
+    /// [`logs::read_page`](crate::logs::read_page).
+    pub fn read_log_file(
+        &self,
+        rotation: u32,
+        offset: u64,
+        limit: usize,
+    ) -> Result<LogPage, SidecarError> {
+        let path = self
+            .backend_log_path
+            .as_deref()
+            .ok_or("The backend does not write a log file")?;
+        let path = logs::rotated_path(path, rotation);
+        logs::read_page(&path, offset, limit)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e).into())
+    }
+
+    /// Rotate the backend's log file now, whatever its size, so it starts
+    /// empty. Returns the path the old contents were moved to. See
+    /// [`logs::rotate`](crate::logs::rotate).
+    pub fn rotate_log_file(&self) -> Result<PathBuf, SidecarError> {
+        let path = self
+            .backend_log_path
+            .as_deref()
+            .ok_or("The backend does not write a log file")?;
+        let rotated = logs::rotate(path)
+            .map_err(|e| format!("Failed to rotate {}: {}", path.display(), e))?;
+        info!("Rotated backend log to {}", rotated.display());
+        Ok(rotated)
+    }
+
+    /// Token sent to the backend, if one is configured. The `get_api_token`
+    /// command returns it to the frontend.
+    pub fn auth_token(&self) -> Option<&str> {
+        self.auth_token.as_deref()
+    }
+
+    /// Generate a new auth token, hand it to the running backend on the
+    /// [rotation endpoint](Self::with_token_rotation) and use it from now
+    /// on, emitting `token-rotated`. A stopped backend gets it on its next
+    /// start.
+    ///
+    /// The token only changes here once the backend has accepted it, so a
+    /// failed delivery leaves the old one in use.
+    pub async fn rotate_auth_token(&mut self) -> Result<(), SidecarError> {
+        let current = self
+            .auth_token
+            .clone()
+            .ok_or("No auth token is configured")?;
+        let (path, overlap) = self
+            .token_rotation
+            .clone()
+            .ok_or("Token rotation is not configured")?;
+        let token = generate_token()?;
+
+        if let Ok(port) = self.running_port() {
+            let body = serde_json::json!({
+                "token": token,
+                "overlap_ms": overlap.as_millis() as u64,
+            })
+            .to_string();
+            let authorization = format!("Bearer {}", current);
+            let headers = http::control_headers(
+                Some(&authorization),
+                &[("Content-Type", "application/json")],
+            );
+            let response = http::request(
+                port,
+                "POST",
+                &path,
+                &headers,
+                Some(body.as_bytes()),
+                Duration::from_secs(5),
+            )
+            .await?;
+            if !(200..300).contains(&response.status) {
+                return Err(format!("Token rotation request returned {}", response.status).into());
+            }
+        }
+
+        self.auth_token = Some(token);
+        info!("Rotated the API token");
+        if let Some(app) = &self.app {
+            let _ = emit(
+                app,
+                self.event_target.as_deref(),
+                &self.events.token_rotated,
+                TokenRotated {
+                    rotated_at_ms: now_ms(),
+                    overlap_ms: overlap.as_millis() as u64,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the backend process is up (or, if external, reachable),
+    /// whatever else the status says.
+    fn process_up(&self) -> bool {
+        match self.ownership {
+            Ownership::Spawned => self.child.as_ref().is_some_and(|p| !p.has_exited()),
+            Ownership::External => self.external_reachable,
+        }
+    }
+
+    /// Who owns the backend process.
+    pub fn ownership(&self) -> Ownership {
+        self.ownership
+    }
+
+    /// Current status of the backend.
+    pub fn status(&self) -> BackendStatus {
+        let running = self.process_up();
+        BackendStatus {
+            status: if *self.restarting.borrow() {
+                SidecarStatus::Restarting
+            } else if self.child.as_ref().is_some_and(|p| p.compromised) {
+                SidecarStatus::Compromised
+            } else if self.maintenance {
+                SidecarStatus::Maintenance
+            } else if running && self.using_fallback {
+                SidecarStatus::RunningFallback
+            } else if running {
+                SidecarStatus::Running
+            } else {
+                SidecarStatus::Stopped
+            },
+            requests: if self.draining.load(Ordering::SeqCst) {
+                RequestState::Draining
+            } else if *self.restarting.borrow() {
+                RequestState::Gated
+            } else {
+                RequestState::Open
+            },
+            in_flight_requests: *self.in_flight_requests.borrow(),
+            maintenance: self.maintenance,
+            port: self.port(),
+            ownership: self.ownership,
+            binary: self.spawn_binary().to_string(),
+            backend_version: self.backend_version(),
+            last_exit: self.last_exit(),
+            last_exit_message: self.last_exit().and_then(|exit| self.exit_message(&exit)),
+            storage_writable: self.storage.as_ref().map(Storage::writable),
+        }
+    }
+
+    /// Paths sidecar resolution would consider for the configured binary,
+    /// and whether each exists.
+    pub fn debug_resolve_sidecar(&self, app: &AppHandle) -> Vec<SidecarCandidate> {
+        resolve::sidecar_candidates(app, &self.binary)
+    }
+
+    /// Path, size, modification time and SHA-256 of the sidecar binary that
+    /// would be spawned. The hash is computed on first use and cached until
+    /// the file changes.
+    pub async fn binary_info(&mut self, app: &AppHandle) -> Result<BinaryInfo, SidecarError> {
+        let path = resolve::sidecar_candidates(app, &self.binary)
+            .into_iter()
+            .find(|c| c.spawned && c.exists)
+            .map(|c| c.path)
+            .ok_or_else(|| format!("Sidecar binary {} could not be found", self.binary))?;
+        let metadata = std::fs::metadata(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64);
+
+        if let Some(info) = self.binary_info.as_ref().filter(|info| {
+            info.path == path && info.size == metadata.len() && info.modified_ms == modified_ms
+        }) {
+            return Ok(info.clone());
+        }
+
+        let hashed = path.clone();
+        let sha256 = tauri::async_runtime::spawn_blocking(move || sha256_file(&hashed))
+            .await
+            .map_err(|e| format!("Failed to hash sidecar binary: {}", e))?
+            .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))?;
+        let info = BinaryInfo {
+            path,
+            size: metadata.len(),
+            sha256,
+            modified_ms,
+        };
+        self.binary_info = Some(info.clone());
+        Ok(info)
+    }
+
+    /// Check the configuration without spawning anything: the sidecar
+    /// resolves, the port is usable, required environment variables are set
+    /// and the working directory exists.
+    pub fn validate(&self, app: &AppHandle) -> ValidationReport {
+        let mut checks = Vec::new();
+        let mut check = |name: &str, passed: bool, detail: String| {
+            checks.push(ValidationCheck {
+                name: name.into(),
+                passed,
+                detail,
+            });
+        };
+
+        let running = match self.status().status {
+            SidecarStatus::Maintenance => self.process_up(),
+            status => status.is_running(),
+        };
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, self.port()));
+        match self.ownership {
+            Ownership::Spawned => {
+                match resolve::sidecar_candidates(app, &self.binary)
+                    .into_iter()
+                    .find(|c| c.spawned)
+                {
+                    Some(c) if c.exists => check("sidecar", true, c.path.display().to_string()),
+                    Some(c) => check(
+                        "sidecar",
+                        false,
+                        format!("{} does not exist", c.path.display()),
+                    ),
+                    None => check(
+                        "sidecar",
+                        false,
+                        "could not determine the app executable directory".into(),
+                    ),
+                }
+
+                if running {
+                    check(
+                        "port",
+                        true,
+                        format!("port {} is in use by the running sidecar", self.port()),
+                    );
+                } else {
+                    match TcpListener::bind(addr) {
+                        Ok(_) => check("port", true, format!("port {} is available", self.port())),
+                        Err(e) => check(
+                            "port",
+                            false,
+                            format!("port {} is not available: {}", self.port(), e),
+                        ),
+                    }
+                }
+            }
+            Ownership::External => {
+                match StdTcpStream::connect_timeout(&addr, Duration::from_secs(1)) {
+                    Ok(_) => check(
+                        "port",
+                        true,
+                        format!("external API server is listening on port {}", self.port()),
+                    ),
+                    Err(e) => check(
+                        "port",
+                        false,
+                        format!("nothing is listening on port {}: {}", self.port(), e),
+                    ),
+                }
+            }
+        }
+
+        for name in &self.required_env {
+            let set = self.env.contains_key(name)
+                || self.env_defaults.contains_key(name)
+                || (self.env_inheritance.passes(name) && std::env::var_os(name).is_some());
+            let detail = if set {
+                format!("{} is set", name)
+            } else {
+                format!("{} is not set", name)
+            };
+            check(&format!("env:{}", name), set, detail);
+        }
+
+        if let Some(dir) = &self.current_dir {
+            let detail = if dir.is_dir() {
+                format!("{} exists", dir.display())
+            } else {
+                format!("{} is not a directory", dir.display())
+            };
+            check("working_dir", dir.is_dir(), detail);
+        }
+
+        ValidationReport {
+            ok: checks.iter().all(|c| c.passed),
+            checks,
+        }
+    }
+
+    /// The last `limit` captured output lines (all of them if `None`),
+    /// oldest first. `stream` keeps only stdout or stderr lines, e.g. the
+    /// stderr tail for a crash report; `flat` returns just the text of each
+    /// line.
+    pub fn recent_logs(
+        &self,
+        limit: Option<usize>,
+        stream: Option<LogStream>,
+        flat: bool,
+    ) -> RecentLogs {
+        let entries = lock(&self.logs).recent(limit.unwrap_or(usize::MAX), stream);
+        if flat {
+            RecentLogs::Flat(entries.into_iter().map(|entry| entry.text).collect())
+        } else {
+            RecentLogs::Structured(entries)
+        }
+    }
+
+    /// How the most recent sidecar process exited, if one has.
+    pub fn last_exit(&self) -> Option<SidecarExit> {
+        *lock(&self.last_exit)
+    }
+
+    /// The [registered message](Self::with_exit_message) for a crash with
+    /// `exit`'s code.
+    fn exit_message(&self, exit: &SidecarExit) -> Option<String> {
+        lookup_exit_message(&self.exit_messages, exit)
+    }
+
+    /// Wait for the sidecar to finish on its own and return how it exited,
+    /// for backends that run a job and exit rather than serve forever.
+    ///
+    /// An exit with code 0 counts as clean, so auto-restart leaves it alone;
+    /// any other exit is a crash and recovered as usual. The manager stays
+    /// borrowed until the process exits, so avoid calling this on a shared
+    /// manager that commands also need.
+    pub async fn wait(&mut self) -> Result<SidecarExit, SidecarError> {
+        let process = self.child.as_mut().ok_or(SidecarError::NotRunning)?;
+        process.awaited.store(true, Ordering::Relaxed);
+        // An error means the output task is gone, i.e. the process exited.
+        let _ = process.exited.wait_for(|exited| *exited).await;
+        let exit = self
+            .last_exit()
+            .ok_or("API server exit status was not reported")?;
+        if exit.clean {
+            self.intentionally_stopped = true;
+        }
+        Ok(exit)
+    }
+
+    /// Up to `max_results` of the most recent captured lines containing
+    /// `query`, oldest first.
+    pub fn search_logs(
+        &self,
+        query: &str,
+        case_insensitive: bool,
+        max_results: usize,
+    ) -> Vec<LogEntry> {
+        lock(&self.logs).search(query, case_insensitive, max_results)
+    }
+
+    /// Version reported by the backend, once it has been fetched.
+    pub fn backend_version(&self) -> Option<String> {
+        lock(&self.backend_version).clone()
+    }
+
+    /// Run the configured stack dumper against the sidecar and return its
+    /// output, also saving it as `stack-<ms>.txt` in the app log directory.
+    pub async fn dump_stack(&self) -> Result<StackDump, SidecarError> {
+        let dumper = self
+            .stack_dumper
+            .clone()
+            .ok_or("No stack dumper is configured")?;
+        let pid = self.pid().ok_or(SidecarError::NotRunning)?;
+        let args: Vec<String> = dumper
+            .args
+            .iter()
+            .map(|arg| arg.replace("{pid}", &pid.to_string()))
+            .collect();
+
+        let mut command = StdCommand::new(&dumper.program);
+        command.args(&args);
+        // The dumper is attached to the backend, so it mustn't outlive the
+        // timeout
+        let output = process::output_within(command, STACK_DUMP_TIMEOUT)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => format!(
+                    "{} is not installed or not on PATH, install it to dump stacks",
+                    dumper.program
+                ),
+                _ => format!("Failed to run {}: {}", dumper.program, e),
+            })?
+            .ok_or_else(|| {
+                format!(
+                    "{} did not finish within {:?}, killed it",
+                    dumper.program, STACK_DUMP_TIMEOUT
+                )
+            })?;
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        if !output.status.success() {
+            return Err(format!(
+                "{} failed ({}): {}",
+                dumper.program,
+                output.status,
+                text.trim()
+            )
+            .into());
+        }
+
+        let path = self
+            .app
+            .as_ref()
+            .and_then(storage::log_dir)
+            .map(|dir| dir.join(format!("stack-{}.txt", now_ms())))
+            .filter(|path| match std::fs::write(path, &text) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Failed to save stack dump to {}: {}", path.display(), e);
+                    false
+                }
+            });
+        if let Some(path) = &path {
+            info!("Saved API server stack dump to {}", path.display());
+        }
+        Ok(StackDump { output: text, path })
+    }
+
+    /// Ask the backend for a heap dump, wait for it to write the file and
+    /// copy it into the app log directory. Returns the copy's path.
+    pub async fn collect_dump(&self) -> Result<PathBuf, SidecarError> {
+        let (trigger, source) = self
+            .backend_dump
+            .clone()
+            .ok_or("No backend dump is configured")?;
+        let port = self.running_port()?;
+        let previous = std::fs::metadata(&source)
+            .and_then(|meta| meta.modified())
+            .ok();
+
+        match &trigger {
+            DumpTrigger::Signal(signal) => {
+                let pid = self.pid().ok_or(SidecarError::NotRunning)?;
+                if !process::send_signal(pid, *signal) {
+                    return Err(
+                        format!("Failed to send signal {} to the API server", signal).into(),
+                    );
+                }
+            }
+            DumpTrigger::Endpoint(path) => {
+                let authorization = self.authorization();
+                let headers = http::control_headers(authorization.as_deref(), &[]);
+                let response =
+                    http::request(port, "POST", path, &headers, None, BACKEND_DUMP_TIMEOUT).await?;
+                if !(200..300).contains(&response.status) {
+                    return Err(format!("Dump request returned {}", response.status).into());
+                }
+            }
+        }
+        info!(
+            "Requested a backend dump, waiting for {}...",
+            source.display()
+        );
+
+        // Done once the file is newer than before and stops growing
+        let deadline = Instant::now() + BACKEND_DUMP_TIMEOUT;
+        let mut last_len = None;
+        loop {
+            let meta = std::fs::metadata(&source).ok();
+            let fresh = meta
+                .as_ref()
+                .filter(|meta| meta.modified().ok() != previous)
+                .map(std::fs::Metadata::len);
+            if fresh.is_some() && fresh == last_len {
+                break;
+            }
+            last_len = fresh;
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "The backend did not write {} within {:?}",
+                    source.display(),
+                    BACKEND_DUMP_TIMEOUT
+                )
+                .into());
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        let dir = self
+            .app
+            .as_ref()
+            .and_then(storage::log_dir)
+            .ok_or("The app log directory is not available")?;
+        let name = source
+            .file_name()
+            .map_or("dump".into(), |name| name.to_string_lossy());
+        let target = dir.join(format!("dump-{}-{}", now_ms(), name));
+        std::fs::copy(&source, &target)
+            .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+        info!("Saved API server dump to {}", target.display());
+        Ok(target)
+    }
+
+    /// Compact metrics for UIs that poll rather than listen for events.
+    ///
+    /// The health probe and RSS reading are cached for a second. There is no
+    /// probe under [`ReadinessCheck::None`], so `last_probe_latency_ms` stays
+    /// `None`.
+    pub async fn metrics(&mut self) -> BackendMetrics {
+        let status = self.status().status;
+        let pid = self.pid();
+
+        let stale = self
+            .metrics_sample
+            .as_ref()
+            .is_none_or(|s| s.taken_at.elapsed() >= METRICS_CACHE_TTL);
+        if stale {
+            let probe_latency_ms =
+                if status.is_running() && self.readiness_check == ReadinessCheck::Probe {
+                    self.probe_latency_ms().await
+                } else {
+                    None
+                };
+            self.metrics_sample = Some(MetricsSample {
+                taken_at: Instant::now(),
+                probe_latency_ms,
+                rss_bytes: pid.and_then(stats::rss_bytes),
+            });
+        }
+        let sample = self.metrics_sample.as_ref();
+
+        BackendMetrics {
+            status,
+            uptime_secs: self
+                .child
+                .as_ref()
+                .filter(|p| !p.has_exited())
+                .map_or(0, |p| p.started_at.elapsed().as_secs()),
+            restart_count: self.restart_count,
+            crash_count: self.crash_count.load(Ordering::Relaxed),
+            last_probe_latency_ms: sample.and_then(|s| s.probe_latency_ms),
+            rss_bytes: sample.and_then(|s| s.rss_bytes),
+            probe_attempts: self.counters.probe_attempts.load(Ordering::Relaxed),
+            probe_failures: self.counters.probe_failures.load(Ordering::Relaxed),
+            successful_startups: self.counters.successful_startups.load(Ordering::Relaxed),
+            crash_restarts: self.counters.crash_restarts.load(Ordering::Relaxed),
+            warmup_latency_ms: *lock(&self.warmup_latency_ms),
+        }
+    }
+
+    /// One health monitor tick: stop the backend if it has been idle too
+    /// long, and restart it if it is over its memory limit.
+    pub async fn monitor_tick(&mut self) {
+        if self.maintenance {
+            return;
+        }
+        if self.ownership == Ownership::External {
+            self.check_external().await;
+            return;
+        }
+        if self.stop_if_expired().await || self.stop_if_idle().await {
+            return;
+        }
+        self.restart_if_over_memory().await;
+        self.check_health().await;
+        self.verify_identity().await;
+        self.reset_backoff_if_stable();
+    }
+
+    /// Check the backend is set up to prove who it is: each spawned process
+    /// gets a random `TETHER_INSTANCE_ID` and must echo it in the `header`
+    /// response header of the health endpoint.
+    ///
+    /// The health monitor checks it on every tick once the process has had
+    /// its startup timeout. If someone else answers on the port, e.g. after
+    /// the backend died and another program took it, the status becomes
+    /// [`SidecarStatus::Compromised`], requests stop being proxied and
+    /// `backend-identity-mismatch` is emitted. Off by default.
+    ///
+    /// The check is a request to the backend, so it is skipped under
+    /// [`ReadinessCheck::None`]; enable one or the other, not both.
+    pub fn with_identity_check(mut self, header: impl Into<String>) -> Self {
+        self.identity_header = Some(header.into());
+        self
+    }
+
+    /// Run the [identity check](Self::with_identity_check).
+    async fn verify_identity(&mut self) {
+        let Some(header) = self.identity_header.clone() else {
+            return;
+        };
+        if self.readiness_check != ReadinessCheck::Probe {
+            return;
+        }
+        // Also once our process has exited: whatever answers on the port
+        // then can't be it
+        let Some(expected) = self
+            .child
+            .as_ref()
+            .filter(|p| !p.compromised)
+            .filter(|p| p.started_at.elapsed() >= self.startup_timeout)
+            .and_then(|p| p.instance_id.clone())
+        else {
+            return;
+        };
+        if *self.restarting.borrow() {
+            return;
+        }
+
+        let port = self.port();
+        let authorization = self.probe_authorization();
+        let headers = http::control_headers(authorization.as_deref(), &[]);
+        let response = match http::request(
+            port,
+            "GET",
+            &self.health_path,
+            &headers,
+            None,
+            Duration::from_secs(1),
+        )
+        .await
+        {
+            Ok(response) => response,
+            // Unreachable is for the health check to judge
+            Err(_) => return,
+        };
+        if response.header(&header) == Some(expected.as_str()) {
+            return;
+        }
+
+        error!(
+            "Server on port {} did not identify as our API server, no longer trusting it",
+            port
+        );
+        if let Some(process) = self.child.as_mut() {
+            process.compromised = true;
+        }
+        let _ = self.lifecycle.send(LifecycleEvent::Compromised(port));
+        if let Some(app) = &self.app {
+            let _ = emit(
+                app,
+                self.event_target.as_deref(),
+                &self.events.identity_mismatch,
+                IdentityMismatch { port },
+            );
+        }
+    }
+
+    /// Check that an external backend still accepts connections, since the
+    /// developer may restart it at any time, and broadcast
+    /// [`LifecycleEvent::Failed`] or [`LifecycleEvent::Ready`] when that
+    /// changes.
+    async fn check_external(&mut self) {
+        let port = self.port();
+        let reachable = matches!(
+            tokio::time::timeout(
+                Duration::from_secs(1),
+                TcpStream::connect(("127.0.0.1", port))
+            )
+            .await,
+            Ok(Ok(_))
+        );
+        if reachable == self.external_reachable {
+            return;
+        }
+        self.external_reachable = reachable;
+        if reachable {
+            info!("External API server on port {} is reachable again", port);
+            if let Some(app) = self.app.clone() {
+                self.fetch_backend_version(&app, port);
+            }
+            let _ = self.lifecycle.send(LifecycleEvent::Ready(port));
+        } else {
+            warn!("External API server on port {} is not reachable", port);
+            let _ = self.lifecycle.send(LifecycleEvent::Failed(format!(
+                "external API server on port {} is not reachable",
+                port
+            )));
+        }
+    }
+
+    /// Probe the backend and take the unhealthy action once too many probes
+    /// in a row have failed.
+    async fn check_health(&mut self) {
+        let Some(action) = self.on_unhealthy.clone() else {
+            return;
+        };
+        if self.readiness_check != ReadinessCheck::Probe {
+            return;
+        }
+        let settled = self
+            .child
+            .as_ref()
+            .filter(|p| !p.has_exited())
+            .is_some_and(|p| p.started_at.elapsed() >= self.startup_timeout);
+        if self.ownership == Ownership::External || !settled || *self.restarting.borrow() {
+            self.failed_probes = 0;
+            return;
+        }
+
+        if self.probe_latency_ms().await.is_some() {
+            self.failed_probes = 0;
+            return;
+        }
+        self.failed_probes += 1;
+        if self.failed_probes < UNHEALTHY_AFTER_FAILURES {
+            return;
+        }
+
+        let unhealthy = Unhealthy {
+            port: self.port(),
+            failed_probes: self.failed_probes,
+        };
+        warn!(
+            "API server failed {} health probes in a row ({:?})",
+            unhealthy.failed_probes, action
+        );
+        self.failed_probes = 0;
+        if let Some(app) = &self.app {
+            let _ = emit(
+                app,
+                self.event_target.as_deref(),
+                &self.events.unhealthy,
+                &unhealthy,
+            );
+        }
+        match action {
+            UnhealthyAction::Restart => {
+                if let Err(e) = self.restart_for(ShutdownReason::Unhealthy).await {
+                    error!("Failed to restart API server: {}", e);
+                }
+            }
+            UnhealthyAction::Notify => {}
+            UnhealthyAction::RunHook(hook) => hook(&unhealthy),
+        }
+    }
+
+    /// Stop the sidecar if it has used up its maximum runtime. Returns
+    /// whether it was stopped.
+    async fn stop_if_expired(&mut self) -> bool {
+        let Some((max_runtime, policy)) = self.max_runtime else {
+            return false;
+        };
+        let Some(first_started) = self.first_started else {
+            return false;
+        };
+        let running = self.pid().is_some();
+        let now = Instant::now();
+        if running {
+            self.runtime_used += now - self.last_runtime_tick.unwrap_or(now);
+        }
+        self.last_runtime_tick = running.then_some(now);
+
+        let runtime = match policy {
+            RuntimePolicy::WallClock => first_started.elapsed(),
+            RuntimePolicy::Cumulative => self.runtime_used,
+        };
+        if self.runtime_expired || runtime < max_runtime {
+            return false;
+        }
+
+        info!(
+            "API server reached its maximum runtime of {:?}, stopping...",
+            max_runtime
+        );
+        self.runtime_expired = true;
+        if let Err(e) = self.stop_for(ShutdownReason::RuntimeExpired).await {
+            error!("Failed to stop expired API server: {}", e);
+        }
+        if let Some(app) = &self.app {
+            let _ = emit(
+                app,
+                self.event_target.as_deref(),
+                &self.events.runtime_expired,
+                RuntimeExpired {
+                    max_runtime_secs: max_runtime.as_secs(),
+                },
+            );
+        }
+        true
+    }
+
+    /// Stop the sidecar if nothing has used it within the idle timeout.
+    /// Returns whether it was stopped.
+    async fn stop_if_idle(&mut self) -> bool {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return false;
+        };
+        if self.ownership == Ownership::External
+            || self.pid().is_none()
+            || self.last_activity.elapsed() < idle_timeout
+        {
+            return false;
+        }
+
+        info!(
+            "API server idle for {:?}, stopping until it is needed...",
+            idle_timeout
+        );
+        match self.stop_for(ShutdownReason::Idle).await {
+            Ok(_) => {
+                self.idle_stopped = true;
+                true
+            }
+            Err(e) => {
+                error!("Failed to stop idle API server: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Restart the sidecar if it is over its memory limit.
+    async fn restart_if_over_memory(&mut self) {
+        let Some(max_rss_bytes) = self.max_rss_bytes else {
+            return;
+        };
+        let Some(pid) = self.pid() else {
+            return;
+        };
+        let Some(rss_bytes) = stats::rss_bytes(pid) else {
+            return;
+        };
+        if rss_bytes <= max_rss_bytes {
+            return;
+        }
+
+        warn!(
+            "API server is using {} bytes (limit {}), restarting...",
+            rss_bytes, max_rss_bytes
+        );
+        if let Err(e) = self.restart_for(ShutdownReason::MemoryLimit).await {
+            error!("Failed to restart API server: {}", e);
+            return;
+        }
+        if let Some(app) = &self.app {
+            let _ = emit(
+                app,
+                self.event_target.as_deref(),
+                &self.events.restarted_oom,
+                MemoryLimitExceeded {
+                    rss_bytes,
+                    max_rss_bytes,
+                },
+            );
+        }
+    }
+
+    /// Round-trip time of a health probe, or `None` if it failed.
+    async fn probe_latency_ms(&self) -> Option<u64> {
+        let authorization = self.probe_authorization();
+        let headers = http::control_headers(authorization.as_deref(), &[]);
+        let started = Instant::now();
+        let result = http::request(
+            self.port(),
+            "GET",
+            &self.health_path,
+            &headers,
+            None,
+            Duration::from_secs(1),
+        )
+        .await;
+        debug!(
+            "Health probe {} on port {}: {:?}",
+            self.health_path,
+            self.port(),
+            result.as_ref().map(|response| response.status)
+        );
+        let healthy = result.is_ok_and(|response| {
+            if self.health_statuses.is_empty() {
+                (200..300).contains(&response.status)
+            } else {
+                self.health_statuses.contains(&response.status)
+            }
+        });
+        self.counters.record_probe(healthy);
+        healthy.then(|| started.elapsed().as_millis() as u64)
+    }
+
+    /// Replace the stored app handle used by restarts, crash recovery and
+    /// wakes, e.g. after the app recreated it.
+    pub fn set_app_handle(&mut self, app: AppHandle) {
+        self.app = Some(app);
+    }
+
+    /// Store the app handle and probe storage without spawning anything,
+    /// so the app can decide later whether to [`start`](Self::start) the
+    /// backend at all. `start` does this itself if it hasn't been done.
+    pub fn initialize(&mut self, app: &AppHandle) {
+        self.app = Some(app.clone());
+        if self.storage.is_none() {
+            self.storage = Some(Storage::probe(app));
+            if self.backend_log.is_some() {
+                self.backend_log_path =
+                    storage::log_dir(app).map(|dir| dir.join(BACKEND_LOG_FILE_NAME));
+            }
+        }
+    }
+
+    /// Start the sidecar process.
+    pub async fn start(&mut self, app: &AppHandle) -> Result<String, SidecarError> {
+        self.initialize(app);
+
+        if self.ownership == Ownership::External {
+            info!("Using external API server on port {}", self.port());
+            self.fetch_backend_version(app, self.port());
+            return Ok(format!("Using external API server on port {}", self.port()));
+        }
+
+        if self.child.as_ref().is_some_and(|p| !p.has_exited()) {
+            if self.strict {
+                return Err(SidecarError::AlreadyRunning);
+            }
+            return Ok("API server is already running".into());
+        }
+        if self.runtime_expired {
+            return Err("API server has reached its maximum runtime".into());
+        }
+
+        if let Some(policy) = self.single_instance {
+            if !self.claim_instance_lock()? {
+                return self.defer_to_other_instance(app, policy);
+            }
+        }
+
+        let started = self.launch(app).await;
+        // Another instance may take over if this one failed to start
+        if started.is_err() {
+            self.instance_lock = None;
+        }
+        started
+    }
+
+    /// The part of [`start`](Self::start) after the instance lock, if any,
+    /// is claimed.
+    async fn launch(&mut self, app: &AppHandle) -> Result<String, SidecarError> {
+        self.run_pre_start(app).await?;
+        info!("Starting API server on port {}...", self.port());
+
+        self.assign_named_ports()?;
+        let child = self.spawn_with_fallback(app).await?;
+        self.idle_stopped = false;
+        self.intentionally_stopped = false;
+        self.draining.store(false, Ordering::SeqCst);
+        self.mark_activity();
+
+        if self.run_mode == RunMode::OneShot {
+            let since_ms = child.started_ms;
+            self.child = Some(child);
+            let exit = self.wait().await?;
+            if !exit.clean {
+                return Err(self.startup_failure(format!("API server job {}", exit), since_ms));
+            }
+            info!("API server job {}", exit);
+            return Ok(format!("API server job {}", exit));
+        }
+
+        if self.port_discovery.is_some() {
+            match assigned_port(&mut child.port.clone(), self.startup_timeout).await {
+                Ok(port) => self.port.store(port, Ordering::Release),
+                Err(e) => {
+                    let since_ms = child.started_ms;
+                    child.stopping.store(true, Ordering::Relaxed);
+                    let _ = kill_sidecar(child.child);
+                    return Err(self.startup_failure(
+                        format!("API server did not report its port ({})", e),
+                        since_ms,
+                    ));
+                }
+            }
+        }
+
+        self.fetch_backend_version(app, self.port());
+        self.child = Some(child);
+        self.first_started.get_or_insert_with(Instant::now);
+        self.record_instance_port();
+        info!("API server started successfully on port {}", self.port());
+        Ok(format!("API server started on port {}", self.port()))
+    }
+
+    /// Run a [`RunMode::OneShot`] sidecar to completion and return its exit
+    /// and output, whether or not it succeeded.
+    pub async fn run_once(&mut self, app: &AppHandle) -> Result<RunOutcome, SidecarError> {
+        if self.run_mode != RunMode::OneShot {
+            return Err("run_once requires RunMode::OneShot".into());
+        }
+        if self.child.as_ref().is_some_and(|p| !p.has_exited()) {
+            return Err(SidecarError::AlreadyRunning);
+        }
+
+        let since_ms = now_ms();
+        match self.start(app).await {
+            Ok(_) | Err(SidecarError::StartupFailed { .. }) => {}
+            Err(e) => return Err(e),
+        }
+        let exit = self
+            .last_exit()
+            .ok_or("API server exit status was not reported")?;
+        Ok(RunOutcome {
+            exit,
+            output: lock(&self.logs).since(since_ms),
+        })
+    }
+
+    /// Stop the sidecar process immediately by force-killing it.
+    ///
+    /// Use [`stop_async`](Self::stop_async) to walk the configured
+    /// shutdown steps instead. Stopping a stopped sidecar does nothing, so
+    /// this is safe to call from both `CloseRequested` and `Drop`.
+    ///
+    /// This doesn't block until the killed process has been reaped; the
+    /// next start waits for that instead.
+    pub fn stop(&mut self) -> Result<String, SidecarError> {
+        self.kill(ShutdownReason::Requested)
+    }
+
+    /// Force-kill the sidecar, see [`stop`](Self::stop).
+    fn kill(&mut self, reason: ShutdownReason) -> Result<String, SidecarError> {
+        if self.ownership == Ownership::External {
+            return Ok(format!(
+                "API server on port {} is external, leaving it running",
+                self.port()
+            ));
+        }
+        self.intentionally_stopped = true;
+        self.idle_stopped = false;
+
+        if let Some(process) = self.take_running() {
+            info!("Stopping API server ({})...", reason);
+            process.stopping.store(true, Ordering::Relaxed);
+            let exited = process.exited.clone();
+            kill_sidecar(process.child)?;
+            // Reaping is waited for in the background, and by the next spawn
+            tauri::async_runtime::spawn(report_reaped(exited.clone(), self.port()));
+            self.reaping = Some(exited);
+            self.instance_lock = None;
+            let _ = self.lifecycle.send(LifecycleEvent::Stopped(reason));
+            info!("API server stopped");
+            Ok("API server stopped".into())
+        } else {
+            Ok("API server is already stopped".into())
+        }
+    }
+
+    /// Stop the sidecar process by walking the configured [`ShutdownStep`]s.
+    ///
+    /// Like [`stop`](Self::stop), this keeps crash recovery from bringing
+    /// it back until the next `start` or `restart`.
+    pub async fn stop_async(&mut self) -> Result<String, SidecarError> {
+        self.stop_for(ShutdownReason::Requested).await
+    }
+
+    /// [`stop_async`](Self::stop_async), telling the backend and
+    /// [`subscribe`](Self::subscribe)rs why.
+    pub async fn stop_for(&mut self, reason: ShutdownReason) -> Result<String, SidecarError> {
+        if self.ownership == Ownership::External {
+            return Ok(format!(
+                "API server on port {} is external, leaving it running",
+                self.port()
+            ));
+        }
+        self.intentionally_stopped = true;
+        self.idle_stopped = false;
+
+        if let Some(process) = self.take_running() {
+            info!("Stopping API server ({})...", reason);
+            let report = self.shut_down(process, self.port(), reason).await?;
+            self.instance_lock = None;
+            let _ = self.lifecycle.send(LifecycleEvent::Stopped(reason));
+            info!("API server stopped");
+            warn_port_held(self.port());
+            Ok(match report.shutdown_requests {
+                0 => "API server stopped".into(),
+                n => format!("API server stopped ({} shutdown request attempts)", n),
+            })
+        } else {
+            Ok("API server is already stopped".into())
+        }
+    }
+
+    /// Check that the port can be bound again, i.e. that stopping the
+    /// sidecar didn't leave it (or a child it spawned) holding the port,
+    /// which would make the next launch fail with "address already in use".
+    /// For tests that stop the sidecar; always fails while it's running.
+    pub fn check_port_released(&self) -> Result<(), SidecarError> {
+        port_released(self.port())
+    }
+
+    /// Wait up to [`REAP_TIMEOUT`] for the process [`stop`](Self::stop)
+    /// killed last to be reaped.
+    async fn wait_reaped(&self) {
+        if let Some(exited) = &self.reaping {
+            let mut exited = exited.clone();
+            let _ = tokio::time::timeout(REAP_TIMEOUT, exited.wait_for(|exited| *exited)).await;
+        }
+    }
+
+    /// Directory for the files that tell this sidecar's instances apart:
+    /// the app data directory if writable, else the temp directory.
+    fn instance_dir(&self) -> PathBuf {
+        self.storage
+            .as_ref()
+            .filter(|storage| storage.writable())
+            .and_then(Storage::dir)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// Take the instance lock unless we already hold it. Returns `false`
+    /// if another instance holds it.
+    fn claim_instance_lock(&mut self) -> Result<bool, SidecarError> {
+        if self.instance_lock.is_some() {
+            return Ok(true);
+        }
+        let path = self.instance_dir().join(format!("{}.lock", self.binary));
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        match file.try_lock() {
+            Ok(()) => {
+                self.instance_lock = Some(file);
+                Ok(true)
+            }
+            Err(std::fs::TryLockError::WouldBlock) => Ok(false),
+            Err(std::fs::TryLockError::Error(e)) => {
+                Err(format!("Failed to lock {}: {}", path.display(), e).into())
+            }
+        }
+    }
+
+    /// Write the port to `<binary>.owner` for other instances to attach to.
+    /// It is a separate file because Windows locks block reading the lock
+    /// file itself.
+    fn record_instance_port(&self) {
+        if self.instance_lock.is_none() {
+            return;
+        }
+        let path = self.instance_dir().join(format!("{}.owner", self.binary));
+        if let Err(e) = std::fs::write(&path, self.port().to_string()) {
+            warn!("Failed to write {}: {}", path.display(), e);
+        }
+    }
+
+    /// File holding the typical startup time, in milliseconds, in the app
+    /// data directory so it carries over between launches.
+    fn startup_history_path(&self) -> Option<PathBuf> {
+        self.storage
+            .as_ref()
+            .and_then(Storage::dir)
+            .map(|dir| dir.join(format!("{}.startup", self.binary)))
+    }
+
+    /// Apply `policy` after finding the instance lock held by someone else.
+    fn defer_to_other_instance(
+        &mut self,
+        app: &AppHandle,
+        policy: SingleInstance,
+    ) -> Result<String, SidecarError> {
+        warn!("API server is already managed by another instance of the app");
+        if policy == SingleInstance::Fail {
+            return Err(SidecarError::AlreadyManaged);
+        }
+        let path = self.instance_dir().join(format!("{}.owner", self.binary));
+        let port = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| contents.trim().parse::<u16>().ok())
+            .filter(|&port| port != 0)
+            .ok_or(SidecarError::AlreadyManaged)?;
+        self.port.store(port, Ordering::Release);
+        self.ownership = Ownership::External;
+        info!(
+            "Attaching to the other instance's API server on port {}",
+            port
+        );
+        self.fetch_backend_version(app, port);
+        Ok(format!("Using external API server on port {}", port))
+    }
+
+    /// Take the sidecar process if it is still running. One that already
+    /// exited is dropped, so stopping twice, or after a crash, sends no
+    /// kill and no second `Stopped` event.
+    fn take_running(&mut self) -> Option<SidecarProcess> {
+        self.child.take().filter(|p| !p.has_exited())
+    }
+
+    /// Walk the shutdown steps for `process`, which listens on `port`.
+    async fn shut_down(
+        &self,
+        process: SidecarProcess,
+        port: u16,
+        reason: ShutdownReason,
+    ) -> Result<ShutdownReport, String> {
+        self.shutdown_plan().run(process, port, reason).await
+    }
+
+    /// The configured shutdown steps, to run without borrowing the manager.
+    fn shutdown_plan(&self) -> ShutdownPlan {
+        ShutdownPlan {
+            steps: self.shutdown_steps.clone(),
+            retries: self.shutdown_retries,
+        }
+    }
+
+    /// Shut the running backend down with the configured
+    /// [`ShutdownStep`]s, as [`stop_async`](Self::stop_async) would, then
+    /// start it again. Reports which step it exited during and how long
+    /// each took, to check the shutdown setup without quitting the app.
+    pub async fn test_shutdown(&mut self) -> Result<ShutdownReport, SidecarError> {
+        let app = self.app.clone().ok_or(SidecarError::NoAppHandle)?;
+        if self.ownership == Ownership::External {
+            return Err("API server is external, it can't be shut down from here".into());
+        }
+        let process = self.take_running().ok_or(SidecarError::NotRunning)?;
+        self.intentionally_stopped = true;
+
+        info!("Testing API server shutdown...");
+        let report = self
+            .shut_down(process, self.port(), ShutdownReason::Restart)
+            .await?;
+        self.instance_lock = None;
+        let _ = self
+            .lifecycle
+            .send(LifecycleEvent::Stopped(ShutdownReason::Restart));
+        info!(
+            "API server exited during {} after {}ms, starting it again",
+            report.terminated_by, report.total_ms
+        );
+        self.start(&app).await.map_err(|e| {
+            format!(
+                "API server exited during {} but did not start again: {}",
+                report.terminated_by, e
+            )
+        })?;
+        Ok(report)
+    }
+
+    /// Ask the backend to reload its configuration without restarting it.
+    ///
+    /// POSTs to the reload endpoint if one is set, falling back to SIGHUP on
+    /// Unix when there is none or the request fails. Returns whether the
+    /// backend acknowledged: a 2xx response, or the signal being delivered.
+    pub async fn reload_config(&self) -> Result<bool, SidecarError> {
+        let port = self.running_port()?;
+
+        if let Some(path) = &self.reload_path {
+            info!("Requesting API server config reload via POST {}...", path);
+            let authorization = self.authorization();
+            let headers = http::control_headers(authorization.as_deref(), &[]);
+            match http::request(port, "POST", path, &headers, None, Duration::from_secs(5)).await {
+                Ok(response) if (200..300).contains(&response.status) => return Ok(true),
+                Ok(response) => warn!("Config reload request returned {}", response.status),
+                Err(e) => warn!("Config reload request failed: {}", e),
+            }
+        }
+
+        let Some(pid) = self.pid() else {
+            return Ok(false);
+        };
+        Ok(hangup_sidecar(pid))
+    }
+
+    /// Write `data` to the running sidecar's stdin.
+    pub fn write_stdin(&mut self, data: &[u8]) -> Result<(), SidecarError> {
+        let process = self
+            .child
+            .as_mut()
+            .filter(|p| !p.has_exited())
+            .ok_or(SidecarError::NotRunning)?;
+        match &mut process.child {
+            SidecarChild::Piped(child) => child
+                .write(data)
+                .map_err(|e| format!("Failed to write to API server stdin: {}", e).into()),
+            SidecarChild::Inherited(_) => Err("API server stdin is inherited, not piped".into()),
+        }
+    }
+
+    /// Change the backend's log level without restarting it. `level` must
+    /// be one of [`LOG_LEVELS`]. The level is remembered and applied again
+    /// to every instance started later.
+    pub async fn set_log_level(&mut self, level: &str) -> Result<String, SidecarError> {
+        let level = level.to_ascii_lowercase();
+        if !LOG_LEVELS.contains(&level.as_str()) {
+            return Err(format!(
+                "Invalid log level {:?}, expected one of {}",
+                level,
+                LOG_LEVELS.join(", ")
+            )
+            .into());
+        }
+        self.log_level = Some(level.clone());
+
+        let port = self.running_port()?;
+        let authorization = self.authorization();
+        send_log_level(port, &self.log_level_path, &level, authorization.as_deref()).await?;
+        Ok(format!("API server log level set to {}", level))
+    }
+
+    /// Restart the sidecar process using the configured [`RestartMode`].
+    pub async fn restart(&mut self) -> Result<String, SidecarError> {
+        self.restart_for(ShutdownReason::Restart).await
+    }
+
+    /// [`restart`](Self::restart), stopping the old process for `reason`.
+    pub(crate) async fn restart_for(
+        &mut self,
+        reason: ShutdownReason,
+    ) -> Result<String, SidecarError> {
+        if self.ownership == Ownership::External {
+            return Err("Cannot restart an external API server".into());
+        }
+
+        let app = self.app.clone().ok_or(SidecarError::NoAppHandle)?;
+        self.restart_count += 1;
+        debug!(
+            "Restarting API server ({:?}, restart #{})",
+            self.restart_mode, self.restart_count
+        );
+
+        if self.overlapping() {
+            let (pending, ready) = self.begin_overlap(&app, reason).await?;
+            let ready = ready.wait(pending.timeout).await;
+            let (message, old) = self.finish_overlap(&app, pending, ready)?;
+            if let Some((old, old_port)) = old {
+                if let Err(e) = self.shut_down(old, old_port, reason).await {
+                    error!("{}", e);
+                }
+            }
+            return Ok(message);
+        }
+
+        self.begin_restart();
+        let result = match self.restart_mode {
+            RestartMode::StopFirst => match self.stop_for(reason).await {
+                Ok(_) => match self.run_restart_hook() {
+                    Ok(()) => {
+                        if !self.restart_grace.is_zero() {
+                            debug!("Waiting {:?} before starting again", self.restart_grace);
+                            tokio::time::sleep(self.restart_grace).await;
+                        }
+                        self.start(&app).await
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            },
+            // Nothing running to overlap with
+            RestartMode::Overlap => self.start(&app).await,
+        };
+        self.end_restart_when_ready();
+        result
+    }
+
+    /// Run the [restart hook](Self::with_restart_hook), if any.
+    fn run_restart_hook(&self) -> Result<(), SidecarError> {
+        let Some(hook) = &self.on_restart_between else {
+            return Ok(());
+        };
+        debug!("Running restart hook");
+        hook().map_err(|e| {
+            error!("Restart hook failed, leaving the API server stopped: {}", e);
+            SidecarError::from(format!("Restart hook failed: {}", e))
+        })
+    }
+
+    /// The settings covered by [`apply_config`](Self::apply_config).
+    pub fn config(&self) -> SidecarConfig {
+        SidecarConfig {
+            binary: self.binary.clone(),
+            env: self.env.clone(),
+            current_dir: self.current_dir.clone(),
+            health_path: self.health_path.clone(),
+            ready_pattern: self.ready_pattern.clone(),
+            startup_timeout_ms: self.startup_timeout.as_millis() as u64,
+            pre_start_command: self.pre_start_command.clone(),
+        }
+    }
+
+    /// Validate `config`, swap all of it in at once and restart the sidecar
+    /// if it is running, so it never runs with half of a change applied.
+    /// An invalid config leaves everything as it was.
+    pub async fn apply_config(
+        &mut self,
+        config: SidecarConfig,
+    ) -> Result<BackendStatus, SidecarError> {
+        self.check_config(&config)?;
+        info!("Applying new API server configuration...");
+        self.set_config(config);
+        if self.child.as_ref().is_some_and(|p| !p.has_exited()) {
+            self.restart_for(ShutdownReason::ConfigChange).await?;
+        }
+        Ok(self.status())
+    }
+
+    /// Like [`apply_config`](Self::apply_config), but keep the running
+    /// process and its port unless a changed setting
+    /// [requires a restart](SidecarConfig::requires_restart). Otherwise
+    /// the backend is asked to [reload](Self::reload_config) instead.
+    pub async fn reload(&mut self, config: SidecarConfig) -> Result<BackendStatus, SidecarError> {
+        self.check_config(&config)?;
+        let running = self.child.as_ref().is_some_and(|p| !p.has_exited());
+        let restart = self.config().requires_restart(&config);
+        self.set_config(config);
+        if running && restart {
+            info!("New API server configuration requires a restart");
+            self.restart_for(ShutdownReason::ConfigChange).await?;
+        } else if running {
+            info!("Applied new API server configuration in place");
+            if !self.reload_config().await? {
+                warn!("API server did not acknowledge the config reload");
+            }
+        }
+        Ok(self.status())
+    }
+
+    /// Fail if `config` can't be applied.
+    fn check_config(&self, config: &SidecarConfig) -> Result<(), SidecarError> {
+        if self.ownership == Ownership::External {
+            return Err("Cannot reconfigure an external API server".into());
+        }
+        check_startup_timeout(Duration::from_millis(config.startup_timeout_ms))?;
+        if !config.health_path.starts_with('/') {
+            return Err(format!("Invalid health path: {:?}", config.health_path).into());
+        }
+        if let Some(dir) = config.current_dir.as_ref().filter(|dir| !dir.is_dir()) {
+            return Err(format!("Working directory {} does not exist", dir.display()).into());
+        }
+        if let Some(app) = &self.app {
+            let resolved = resolve::sidecar_candidates(app, &config.binary)
+                .into_iter()
+                .any(|c| c.spawned && c.exists);
+            if !resolved {
+                return Err(format!("Sidecar binary {} could not be found", config.binary).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Swap in a [checked](Self::check_config) `config`.
+    fn set_config(&mut self, config: SidecarConfig) {
+        self.binary = config.binary;
+        self.env = config.env;
+        self.current_dir = config.current_dir;
+        self.health_path = config.health_path;
+        self.ready_pattern = config.ready_pattern;
+        self.startup_timeout = Duration::from_millis(config.startup_timeout_ms);
+        self.pre_start_command = config
+            .pre_start_command
+            .filter(|command| !command.is_empty());
+    }
+
+    /// Stop the sidecar and start the binary `name` in its place. Later
+    /// restarts keep using `name`.
+    ///
+    /// Fails without touching the running sidecar if `name` doesn't resolve.
+    pub async fn switch_binary(&mut self, name: &str) -> Result<String, SidecarError> {
+        if self.ownership == Ownership::External {
+            return Err("Cannot switch the binary of an external API server".into());
+        }
+
+        let app = self.app.clone().ok_or(SidecarError::NoAppHandle)?;
+        if name == self.binary {
+            return Ok(format!("API server is already using {}", name));
+        }
+        let resolved = resolve::sidecar_candidates(&app, name)
+            .into_iter()
+            .any(|c| c.spawned && c.exists);
+        if !resolved {
+            return Err(format!("Sidecar binary {} could not be found", name).into());
+        }
+
+        info!("Switching API server from {} to {}...", self.binary, name);
+        self.stop_for(ShutdownReason::BinarySwitch).await?;
+        let old_binary = std::mem::replace(&mut self.binary, name.to_string());
+        self.start(&app).await?;
+
+        if let Err(e) = emit(
+            &app,
+            self.event_target.as_deref(),
+            &self.events.binary_changed,
+            BinaryChanged {
+                old_binary,
+                binary: self.binary.clone(),
+            },
+        ) {
+            error!("Failed to emit {}: {}", self.events.binary_changed, e);
+        }
+        Ok(format!("API server switched to {}", name))
+    }
+
+    /// [`spawn`](Self::spawn), retrying errors the OS reports as
+    /// temporary with a growing delay.
+    async fn spawn_retrying(&self, app: &AppHandle, port: u16) -> Result<SidecarProcess, String> {
+        // The process `stop` killed may still hold the port
+        self.wait_reaped().await;
+        let mut attempt = 1;
+        loop {
+            match self.spawn(app, port) {
+                Ok(process) => return Ok(process),
+                Err(e) if e.transient && attempt < self.spawn_attempts => {
+                    let delay = self.spawn_retry_delay * attempt;
+                    warn!(
+                        "{} (attempt {} of {}), retrying in {:?}...",
+                        e.message, attempt, self.spawn_attempts, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.message),
+            }
+        }
+    }
+
+    /// Run the [pre-start command](Self::with_pre_start_command), if any,
+    /// and fail unless it exits successfully.
+    async fn run_pre_start(&self, app: &AppHandle) -> Result<(), SidecarError> {
+        let Some((program, args)) = self
+            .pre_start_command
+            .as_ref()
+            .and_then(|command| command.split_first())
+        else {
+            return Ok(());
+        };
+        info!("Running pre-start command {}...", program);
+        let mut command = StdCommand::new(program);
+        let env = self.command_env(app).map_err(|e| e.message)?;
+        if env.clear {
+            command.env_clear();
+        }
+        command.args(args).envs(env.vars);
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        let output = process::output_within(command, self.startup_timeout)
+            .await
+            .map_err(|e| format!("Failed to run pre-start command {}: {}", program, e))?
+            .ok_or_else(|| {
+                format!(
+                    "Pre-start command {} did not finish within {:?}, killed it",
+                    program, self.startup_timeout
+                )
+            })?;
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        let lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let skip = lines.len().saturating_sub(STDERR_TAIL_LINES);
+        Err(SidecarError::StartupFailed {
+            message: format!("Pre-start command {} failed ({})", program, output.status),
+            failure_reason: None,
+            stderr_tail: lines.into_iter().skip(skip).collect(),
+        })
+    }
+
+    /// Spawn the primary binary. With a [fallback](Self::with_fallback_binary)
+    /// configured, a server also has to become ready within the startup
+    /// timeout; if it can't be spawned, exits or doesn't get ready, it is
+    /// killed and the fallback spawned instead.
+    async fn spawn_with_fallback(
+        &mut self,
+        app: &AppHandle,
+    ) -> Result<SidecarProcess, SidecarError> {
+        self.using_fallback = false;
+        let Some(fallback) = self.fallback_binary.clone() else {
+            return Ok(self.spawn_retrying(app, self.port()).await?);
+        };
+        let failure = match self.spawn_retrying(app, self.port()).await {
+            Ok(process) if self.run_mode == RunMode::OneShot => return Ok(process),
+            Ok(process) => match self.ready_check(&process).wait(self.startup_timeout).await {
+                Ok(_) => return Ok(process),
+                Err(e) => {
+                    let since_ms = process.started_ms;
+                    discard_process(process).await;
+                    self.startup_failure(
+                        format!("API server did not become ready ({})", e),
+                        since_ms,
+                    )
+                    .to_string()
+                }
+            },
+            Err(e) => e,
+        };
+
+        error!("{}, starting fallback {} instead", failure, fallback);
+        self.using_fallback = true;
+        match self.spawn_retrying(app, self.port()).await {
+            Ok(process) => Ok(process),
+            Err(e) => {
+                self.using_fallback = false;
+                Err(format!("{}; fallback {} failed too: {}", failure, fallback, e).into())
+            }
+        }
+    }
+
+    /// The binary the next spawn runs: the primary, or the fallback after
+    /// the primary failed.
+    fn spawn_binary(&self) -> &str {
+        match &self.fallback_binary {
+            Some(fallback) if self.using_fallback => fallback,
+            _ => &self.binary,
+        }
+    }
+
+    /// The `PATH` configured with [`with_search_path`](Self::with_search_path).
+    fn search_path(&self, app: &AppHandle) -> Result<Option<OsString>, SpawnError> {
+        let Some(search_path) = &self.search_path else {
+            return Ok(None);
+        };
+        let (dirs, inherited) = match search_path {
+            SearchPath::Prepend(dirs) => {
+                let inherited = match self.env.get("PATH") {
+                    Some(path) => Some(OsString::from(path)),
+                    None => {
+                        std::env::var_os("PATH").filter(|_| self.env_inheritance.passes("PATH"))
+                    }
+                };
+                (dirs, inherited)
+            }
+            SearchPath::Replace(dirs) => (dirs, None),
+        };
+        let resource_dir = app.path().resource_dir().ok();
+        let mut paths: Vec<PathBuf> = dirs
+            .iter()
+            .map(|dir| match &resource_dir {
+                Some(base) if dir.is_relative() => base.join(dir),
+                _ => dir.clone(),
+            })
+            .collect();
+        if let Some(inherited) = inherited {
+            paths.extend(std::env::split_paths(&inherited));
+        }
+        std::env::join_paths(paths)
+            .map(Some)
+            .map_err(|e| format!("Invalid sidecar PATH: {}", e).into())
+    }
+
+    /// The environment the sidecar and its pre-start command run with.
+    fn command_env(&self, app: &AppHandle) -> Result<CommandEnv, SpawnError> {
+        let inherited = self.env_inheritance.inherited();
+        let clear = inherited.is_some();
+        let mut vars: Vec<(OsString, OsString)> = inherited
+            .into_iter()
+            .flatten()
+            .map(|(name, value)| (name.into(), value))
+            .collect();
+        // Later entries win: defaults, then the configured env, then PATH
+        vars.extend(
+            self.env_defaults
+                .iter()
+                .chain(&self.env)
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+        if let Some(path) = self.search_path(app)? {
+            vars.push(("PATH".into(), path));
+        }
+        Ok(CommandEnv { clear, vars })
+    }
+
+    /// Spawn the sidecar on `port` and forward its output to the console.
+    ///
+    /// Exits that aren't [`clean`](SidecarExit::clean) are counted in
+    /// `crash_count`.
+    fn spawn(&self, app: &AppHandle, port: u16) -> Result<SidecarProcess, SpawnError> {
+        let shell = app.shell();
+        let mut command = shell
+            .sidecar(self.spawn_binary())
+            .map_err(|e| format!("Failed to create sidecar command: {}", e))?;
+        let env = self.command_env(app)?;
+        if env.clear {
+            command = command.env_clear();
+        }
+        command = command.envs(env.vars);
+        let port = if self.port_discovery.is_some() {
+            0
+        } else {
+            port
+        };
+        command = command.args(["--port", &port.to_string()]);
+        for named in &self.named_ports {
+            if let Some(port) = named.assigned {
+                command = command.args([named.arg.clone(), port.to_string()]);
+            }
+        }
+        let port_file = matches!(self.port_discovery, Some(PortDiscovery::File))
+            .then(|| self.instance_dir().join(format!("{}.port", self.binary)));
+        if let Some(path) = &port_file {
+            // A stale file would report the previous instance's port
+            let _ = std::fs::remove_file(path);
+            command = command.env("TETHER_PORT_FILE", path);
+        }
+        if let Some(token) = &self.auth_token {
+            command = command.env("TETHER_API_TOKEN", token);
+        }
+        let instance_id = match &self.identity_header {
+            Some(_) => Some(generate_token().map_err(|e| SpawnError {
+                message: e.to_string(),
+                transient: false,
+            })?),
+            None => None,
+        };
+        if let Some(id) = &instance_id {
+            command = command.env("TETHER_INSTANCE_ID", id);
+        }
+        if let (Some(arg), Some(path)) = (&self.backend_log, &self.backend_log_path) {
+            command = match arg {
+                BackendLogFile::Flag(flag) => command.arg(flag).arg(path),
+                BackendLogFile::Env(name) => command.env(name, path),
+            };
+        }
+        if let Some(dir) = &self.current_dir {
+            command = command.current_dir(dir);
+        }
+        let started_ms = now_ms();
+        let (exited_tx, exited) = watch::channel(false);
+        let stopping = Arc::new(AtomicBool::new(false));
+        let awaited = Arc::new(AtomicBool::new(false));
+        let reporter = self.exit_reporter(&stopping, &awaited, exited_tx);
+        let (ready_tx, ready) = watch::channel(false);
+        // Only servers have a ready check to wait for
+        let (serving_tx, serving) = watch::channel(self.run_mode != RunMode::Server);
+        let (port_tx, port_rx) = watch::channel((port != 0).then_some(port));
+        let port_tx = Arc::new(port_tx);
+        let port_pattern = match &self.port_discovery {
+            Some(PortDiscovery::Pattern(pattern)) => Some(pattern.clone()),
+            _ => None,
+        };
+
+        let mut child = match self.stdio {
+            SidecarStdio::Piped => {
+                let spawned = process::with_umask(self.umask, || command.spawn());
+                let (rx, child) = spawned.map_err(|e| {
+                    let transient = match &e {
+                        tauri_plugin_shell::Error::Io(e) => is_transient(e),
+                        _ => false,
+                    };
+                    SpawnError {
+                        message: format!("Failed to spawn API server: {}", e),
+                        transient,
+                    }
+                })?;
+                let name = self.name.clone();
+                let console = if name == DEFAULT_NAME {
+                    "API".to_string()
+                } else {
+                    format!("API [{}]", name)
+                };
+                let forwarder = LogForwarder::new(
+                    app.clone(),
+                    self.event_target.clone(),
+                    self.events.log.clone(),
+                    self.logs.clone(),
+                    !*serving.borrow(),
+                );
+                let output = OutputTask {
+                    name,
+                    console,
+                    forwarder,
+                    serving: serving.clone(),
+                    log_lines: self.log_lines.clone(),
+                    ready_tx,
+                    ready_pattern: self.ready_pattern.clone(),
+                    port_tx: port_tx.clone(),
+                    port_pattern: port_pattern.clone(),
+                    log_filter: self.log_filter.clone(),
+                    log_levels: self.log_levels.clone(),
+                    reporter,
+                    pid: child.pid(),
+                };
+                tauri::async_runtime::spawn(output.run(rx));
+                SidecarChild::Piped(child)
+            }
+            SidecarStdio::Inherit => {
+                if self.ready_pattern.is_some() {
+                    warn!(
+                        "Ready pattern is ignored with inherited stdio, probing the port instead"
+                    );
+                }
+                if port_pattern.is_some() {
+                    warn!("Port pattern can't be matched with inherited stdio, use PortDiscovery::File");
+                }
+                let mut command: StdCommand = command.into();
+                command
+                    .stdin(Stdio::inherit())
+                    .stdout(Stdio::inherit())
+                    .stderr(Stdio::inherit());
+                let spawned = process::with_umask(self.umask, || command.spawn());
+                let child = spawned.map_err(|e| SpawnError {
+                    message: format!("Failed to spawn API server: {}", e),
+                    transient: is_transient(&e),
+                })?;
+                wait_inherited(child, reporter)
+            }
+        };
+        debug!(
+            "Spawned {} (pid {}) on port {}",
+            self.spawn_binary(),
+            child.pid(),
+            port
+        );
+        if let Some(path) = port_file {
+            tauri::async_runtime::spawn(read_port_file(
+                path,
+                port_tx.clone(),
+                self.startup_timeout,
+                exited.clone(),
+            ));
+        }
+        if self.readiness_check == ReadinessCheck::Probe {
+            tauri::async_runtime::spawn(announce_connecting(
+                app.clone(),
+                self.event_target.clone(),
+                self.events.connecting.clone(),
+                port_rx.clone(),
+                self.startup_timeout,
+                exited.clone(),
+            ));
+        }
+
+        if let Some(handshake) = &self.startup_handshake {
+            let written = match &mut child {
+                SidecarChild::Piped(child) => child.write(handshake).map_err(|e| e.to_string()),
+                SidecarChild::Inherited(_) => Err("stdin is inherited".to_string()),
+            };
+            if let Err(e) = written {
+                stopping.store(true, Ordering::Relaxed);
+                let _ = kill_sidecar(child);
+                return Err(format!("Failed to send startup handshake: {}", e).into());
+            }
+        }
+
+        let cgroup = self
+            .cgroup
+            .as_ref()
+            .and_then(|(path, limits)| Cgroup::join(path, limits, child.pid()));
+        let process = SidecarProcess {
+            child,
+            exited,
+            stopping,
+            awaited,
+            ready: (self.ready_pattern.is_some() && self.stdio == SidecarStdio::Piped)
+                .then_some(ready),
+            port: port_rx,
+            _port_tx: port_tx,
+            started_at: Instant::now(),
+            started_ms,
+            _cgroup: cgroup,
+            instance_id,
+            compromised: false,
+        };
+
+        let _ = self.lifecycle.send(LifecycleEvent::Started);
+        // A job may never listen on its port, so only servers get ready
+        if self.run_mode == RunMode::Server {
+            let startup_history = self.startup_history_path();
+            let typical_startup = startup_history.as_deref().and_then(read_typical_startup);
+            tauri::async_runtime::spawn(announce_startup_progress(
+                app.clone(),
+                self.event_target.clone(),
+                self.events.startup_progress.clone(),
+                typical_startup.unwrap_or(DEFAULT_TYPICAL_STARTUP),
+                serving.clone(),
+                process.exited.clone(),
+            ));
+            let progress = (
+                app.clone(),
+                self.event_target.clone(),
+                self.events.startup_progress.clone(),
+            );
+            let started_at = process.started_at;
+
+            let ready = self.ready_check(&process);
+            let exited = process.exited.clone();
+            let lifecycle = self.lifecycle.clone();
+            let counters = self.counters.clone();
+            let timeout = self.startup_timeout;
+            let log_level = self
+                .log_level
+                .clone()
+                .map(|level| (self.log_level_path.clone(), level, self.authorization()));
+            let warmup = self.warmup.clone().map(|(method, path)| Warmup {
+                method,
+                path,
+                token: self.auth_token.clone(),
+                latency_ms: self.warmup_latency_ms.clone(),
+            });
+            tauri::async_runtime::spawn(async move {
+                let result = ready.wait(timeout).await;
+                serving_tx.send_replace(true);
+                match result {
+                    Ok(port) => {
+                        counters.successful_startups.fetch_add(1, Ordering::Relaxed);
+                        let (app, target, event) = progress;
+                        let _ = emit(
+                            &app,
+                            target.as_deref(),
+                            &event,
+                            StartupProgress { percent: 100 },
+                        );
+                        if let Some(path) = startup_history {
+                            record_startup(&path, typical_startup, started_at.elapsed());
+                        }
+                        let _ = lifecycle.send(LifecycleEvent::Ready(port));
+                        if let Some((path, level, authorization)) = log_level {
+                            let sent =
+                                send_log_level(port, &path, &level, authorization.as_deref()).await;
+                            if let Err(e) = sent {
+                                warn!("Failed to re-apply log level {}: {}", level, e);
+                            }
+                        }
+                        if let Some(warmup) = warmup {
+                            warmup.send(port).await;
+                        }
+                    }
+                    // An exit is reported by the output task instead
+                    Err(e) if !*exited.borrow() => {
+                        let _ = lifecycle.send(LifecycleEvent::Failed(format!(
+                            "API server did not become ready: {}",
+                            e
+                        )));
+                    }
+                    Err(_) => {}
+                }
+            });
+        }
+
+        Ok(process)
+    }
+
+    /// Reports the exit of a process spawned with these flags.
+    fn exit_reporter(
+        &self,
+        stopping: &Arc<AtomicBool>,
+        awaited: &Arc<AtomicBool>,
+        exited_tx: watch::Sender<bool>,
+    ) -> ExitReporter {
+        ExitReporter {
+            stopping: stopping.clone(),
+            awaited: awaited.clone(),
+            crash_count: self.crash_count.clone(),
+            crashed: self.crashed.clone(),
+            last_exit: self.last_exit.clone(),
+            exit_messages: self.exit_messages.clone(),
+            lifecycle: self.lifecycle.clone(),
+            exited_tx,
+        }
+    }
+
+    /// Restart on behalf of the user (e.g. a restart button), ignoring the
+    /// request if another one started or finished within the cooldown.
+    pub async fn user_restart(&mut self) -> Result<String, SidecarError> {
+        if self.restart_cooling_down() {
+            return Ok("restart ignored (cooldown)".into());
+        }
+
+        self.last_user_restart = Some(Instant::now());
+        let result = self.restart().await;
+        self.last_user_restart = Some(Instant::now());
+        result
+    }
+
+    /// [`user_restart`](Self::user_restart) a shared manager, releasing the
+    /// lock as [`restart_shared`](Self::restart_shared) does.
+    pub async fn user_restart_shared(manager: &Mutex<Self>) -> Result<String, SidecarError> {
+        {
+            let mut manager = manager.lock().await;
+            if manager.restart_cooling_down() {
+                return Ok("restart ignored (cooldown)".into());
+            }
+            manager.last_user_restart = Some(Instant::now());
+        }
+        let result = Self::restart_shared(manager).await;
+        manager.lock().await.last_user_restart = Some(Instant::now());
+        result
+    }
+
+    /// Whether a user restart started or finished within the cooldown.
+    fn restart_cooling_down(&self) -> bool {
+        self.last_user_restart
+            .is_some_and(|last| last.elapsed() < self.restart_cooldown)
+    }
+
+    /// Once the backend on `port` is ready, record the version it reports
+    /// and warn if it differs from the desktop app's version.
+    fn fetch_backend_version(&self, app: &AppHandle, port: u16) {
+        if self.readiness_check != ReadinessCheck::Probe {
+            return;
+        }
+        let slot = self.backend_version.clone();
+        let path = self.version_path.clone();
+        let authorization = self.probe_authorization();
+        let app_version = app.package_info().version.to_string();
+        let timeout = self.startup_timeout;
+        *lock(&slot) = None;
+
+        tauri::async_runtime::spawn(async move {
+            let deadline = Instant::now() + timeout;
+            let headers = http::control_headers(authorization.as_deref(), &[]);
+            let response = loop {
+                let result =
+                    http::request(port, "GET", &path, &headers, None, Duration::from_secs(2)).await;
+                debug!(
+                    "Version probe {} on port {}: {:?}",
+                    path,
+                    port,
+                    result.as_ref().map(|response| response.status)
+                );
+                match result {
+                    Ok(response) if (200..300).contains(&response.status) => break response,
+                    Ok(response) if response.status == 404 => {
+                        warn!("Backend has no version endpoint at {}", path);
+                        return;
+                    }
+                    _ if Instant::now() >= deadline => {
+                        error!("Could not fetch backend version from {}", path);
+                        return;
+                    }
+                    _ => tokio::time::sleep(Duration::from_millis(250)).await,
+                }
+            };
+
+            let Some(version) = parse_version(&response.body) else {
+                warn!("Backend version endpoint returned no version");
+                return;
+            };
+            info!("Backend version: {}", version);
+            if version != app_version {
+                warn!(
+                    "backend version {} does not match app version {}",
+                    version, app_version
+                );
+            }
+            *lock(&slot) = Some(version);
+        });
+    }
+
+    /// Whether a restart now overlaps the old and new instances.
+    fn overlapping(&self) -> bool {
+        self.restart_mode == RestartMode::Overlap
+            && self.child.as_ref().is_some_and(|p| !p.has_exited())
+    }
+
+    /// Spawn the replacement for an overlapping restart on a new port. The
+    /// old instance keeps serving, and its status stays as it is, until
+    /// [`finish_overlap`](Self::finish_overlap) switches over.
+    async fn begin_overlap(
+        &mut self,
+        app: &AppHandle,
+        reason: ShutdownReason,
+    ) -> Result<(PendingOverlap, ReadyCheck), SidecarError> {
+        if self.overlap_pending {
+            return Err("A restart is already in progress".into());
+        }
+        let new_port = if self.port_discovery.is_some() {
+            0
+        } else {
+            portpicker::pick_unused_port().ok_or("No available port")?
+        };
+        self.run_pre_start(app).await?;
+        info!("Starting replacement API server on port {}...", new_port);
+
+        self.assign_named_ports()?;
+        let process = self.spawn_retrying(app, new_port).await?;
+        let ready = self.ready_check(&process);
+        self.overlap_pending = true;
+        Ok((
+            PendingOverlap {
+                process,
+                replaces: self.pid(),
+                timeout: self.startup_timeout,
+                reason,
+            },
+            ready,
+        ))
+    }
+
+    /// Switch over to the replacement once `ready` says it is, unless the
+    /// instance it was meant to replace was stopped or replaced meanwhile.
+    /// Returns the old instance and its port, for the caller to shut down.
+    fn finish_overlap(
+        &mut self,
+        app: &AppHandle,
+        pending: PendingOverlap,
+        ready: Result<u16, String>,
+    ) -> Result<(String, Option<(SidecarProcess, u16)>), SidecarError> {
+        self.overlap_pending = false;
+        let since_ms = pending.process.started_ms;
+        let new_port = match ready {
+            Ok(port) => port,
+            Err(e) => {
+                let process = pending.process;
+                process.stopping.store(true, Ordering::Relaxed);
+                let _ = kill_sidecar(process.child);
+                return Err(self.startup_failure(
+                    format!("Replacement API server did not start ({})", e),
+                    since_ms,
+                ));
+            }
+        };
+        let current = self.child.as_ref().filter(|p| !p.has_exited());
+        if current.map(|p| p.child.pid()) != pending.replaces {
+            let process = pending.process;
+            process.stopping.store(true, Ordering::Relaxed);
+            let _ = kill_sidecar(process.child);
+            return Err(format!(
+                "API server was {} during the restart, dropping the replacement ({})",
+                if current.is_some() {
+                    "replaced"
+                } else {
+                    "stopped"
+                },
+                pending.reason
+            )
+            .into());
+        }
+        self.fetch_backend_version(app, new_port);
+
+        let old_port = self.port.swap(new_port, Ordering::AcqRel);
+        let old_child = self.child.replace(pending.process);
+        self.record_instance_port();
+
+        if let Err(e) = emit(
+            app,
+            self.event_target.as_deref(),
+            &self.events.port_changed,
+            PortChanged {
+                old_port,
+                port: new_port,
+            },
+        ) {
+            error!("Failed to emit {}: {}", self.events.port_changed, e);
+        }
+
+        info!("API server restarted on port {}", new_port);
+        Ok((
+            format!("API server restarted on port {}", new_port),
+            old_child.map(|old| {
+                info!("Stopping previous API server on port {}...", old_port);
+                (old, old_port)
+            }),
+        ))
+    }
+
+    /// [`restart`](Self::restart) a shared manager. An
+    /// [overlapping](RestartMode::Overlap) restart releases the lock while
+    /// the replacement gets ready and while the old instance shuts down, so
+    /// the old one keeps answering requests and commands until the switch.
+    pub async fn restart_shared(manager: &Mutex<Self>) -> Result<String, SidecarError> {
+        Self::restart_shared_for(manager, ShutdownReason::Restart).await
+    }
+
+    /// [`restart_shared`](Self::restart_shared), stopping the old process
+    /// for `reason`.
+    pub(crate) async fn restart_shared_for(
+        manager: &Mutex<Self>,
+        reason: ShutdownReason,
+    ) -> Result<String, SidecarError> {
+        let (app, pending, ready) = {
+            let mut manager = manager.lock().await;
+            if manager.ownership == Ownership::External || !manager.overlapping() {
+                return manager.restart_for(reason).await;
+            }
+            let app = manager.app.clone().ok_or(SidecarError::NoAppHandle)?;
+            manager.restart_count += 1;
+            debug!(
+                "Restarting API server ({:?}, restart #{})",
+                manager.restart_mode, manager.restart_count
+            );
+            let (pending, ready) = manager.begin_overlap(&app, reason).await?;
+            (app, pending, ready)
+        };
+
+        let ready = ready.wait(pending.timeout).await;
+
+        let (result, plan) = {
+            let mut manager = manager.lock().await;
+            (
+                manager.finish_overlap(&app, pending, ready),
+                manager.shutdown_plan(),
+            )
+        };
+        let (message, old) = result?;
+        if let Some((old, old_port)) = old {
+            if let Err(e) = plan.run(old, old_port, reason).await {
+                error!("{}", e);
+            }
+        }
+        Ok(message)
+    }
+}
+
+/// The replacement spawned by an overlapping restart, getting ready while
+/// the old instance still serves.
+struct PendingOverlap {
+    process: SidecarProcess,
+    /// PID of the instance it replaces.
+    replaces: Option<u32>,
+    timeout: Duration,
+    reason: ShutdownReason,
+}
+
+impl Drop for SidecarManager {
+    fn drop(&mut self) {
+        let _ = self.kill(ShutdownReason::AppExit);
+    }
+}
+
+/// Lowercase hex SHA-256 of the file at `path`.
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// A random 256-bit token, hex encoded.
+fn generate_token() -> Result<String, SidecarError> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Settings [`SidecarManager::from_env`] reads from `TETHER_*` variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct EnvOverrides {
+    port: Option<u16>,
+    binary: Option<String>,
+    health_path: Option<String>,
+    startup_timeout: Option<Duration>,
+}
+
+impl EnvOverrides {
+    /// Read the variables through `lookup`, failing on an invalid value.
+    fn read(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, SidecarError> {
+        let startup_timeout = env_override(&lookup, "TETHER_STARTUP_TIMEOUT_MS", |value| {
+            value.parse().ok().map(Duration::from_millis)
+        })?;
+        if let Some(timeout) = startup_timeout {
+            check_startup_timeout(timeout)
+                .map_err(|e| format!("Invalid TETHER_STARTUP_TIMEOUT_MS: {}", e))?;
+        }
+        Ok(Self {
+            port: env_override(&lookup, "TETHER_PORT", |value| value.parse().ok())?,
+            binary: env_override(&lookup, "TETHER_BINARY", |value| Some(value.to_string()))?,
+            health_path: env_override(&lookup, "TETHER_HEALTH_PATH", |value| {
+                value.starts_with('/').then(|| value.to_string())
+            })?,
+            startup_timeout,
+        })
+    }
+}
+
+/// Look `name` up and `parse` it. Unset or empty is `None`; a value `parse`
+/// rejects is an error naming the variable.
+fn env_override<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<Option<T>, SidecarError> {
+    let Some(value) = lookup(name).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+    parse(&value)
+        .map(Some)
+        .ok_or_else(|| format!("Invalid value {:?} for {}", value, name).into())
+}
+
+/// Extract a version from a JSON `{"version": ...}` body or plain text.
+fn parse_version(body: &[u8]) -> Option<String> {
+    if let Ok(json) = serde_json::from_slice::<serde_json::Value>(body) {
+        return json.get("version")?.as_str().map(str::to_string);
+    }
+    let text = String::from_utf8_lossy(body).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Lock `mutex`, recovering the guard if a panic poisoned it.
+///
+/// Everything behind these locks stays usable after a panic mid-update, so
+/// one panic must not wedge logging or status for the rest of the session.
+fn lock<T>(mutex: &StdMutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// POST `level` to the backend's log level endpoint, authorized with
+/// `authorization` if there is one.
+async fn send_log_level(
+    port: u16,
+    path: &str,
+    level: &str,
+    authorization: Option<&str>,
+) -> Result<(), String> {
+    let body = serde_json::json!({ "level": level }).to_string();
+    let headers = http::control_headers(authorization, &[("Content-Type", "application/json")]);
+    let response = http::request(
+        port,
+        "POST",
+        path,
+        &headers,
+        Some(body.as_bytes()),
+        Duration::from_secs(5),
+    )
+    .await?;
+    if (200..300).contains(&response.status) {
+        Ok(())
+    } else {
+        Err(format!("Log level request returned {}", response.status))
+    }
+}
+
+/// Reject startup timeouts outside the range allowed at runtime.
+fn check_startup_timeout(timeout: Duration) -> Result<(), SidecarError> {
+    if (MIN_STARTUP_TIMEOUT..=MAX_STARTUP_TIMEOUT).contains(&timeout) {
+        return Ok(());
+    }
+    Err(format!(
+        "Startup timeout must be between {:?} and {:?}, got {:?}",
+        MIN_STARTUP_TIMEOUT, MAX_STARTUP_TIMEOUT, timeout
+    )
+    .into())
+}
+
+/// The request configured with [`SidecarManager::with_warmup`].
+struct Warmup {
+    method: String,
+    path: String,
+    token: Option<String>,
+    latency_ms: Arc<StdMutex<Option<u64>>>,
+}
+
+impl Warmup {
+    async fn send(self, port: u16) {
+        let authorization = self.token.map(|token| format!("Bearer {}", token));
+        let headers = http::control_headers(authorization.as_deref(), &[]);
+        let started = Instant::now();
+        let result = http::request(
+            port,
+            &self.method,
+            &self.path,
+            &headers,
+            None,
+            WARMUP_TIMEOUT,
+        )
+        .await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match result {
+            Ok(response) if (200..300).contains(&response.status) => {
+                info!("Warmup {} {} took {}ms", self.method, self.path, elapsed_ms);
+                *lock(&self.latency_ms) = Some(elapsed_ms);
+            }
+            Ok(response) => warn!(
+                "Warmup {} {} returned {}",
+                self.method, self.path, response.status
+            ),
+            Err(e) => warn!("Warmup {} {} failed: {}", self.method, self.path, e),
+        }
+    }
+}
+
+/// Send `entry` to [`SidecarManager::subscribe_logs`] receivers, if any.
+fn publish_line(log_lines: &broadcast::Sender<LogEntry>, entry: &LogEntry) {
+    if log_lines.receiver_count() > 0 {
+        let _ = log_lines.send(entry.clone());
+    }
+}
+
+/// Signal readiness if `line` contains the ready pattern.
+fn mark_ready(ready: &watch::Sender<bool>, pattern: Option<&str>, line: &str) {
+    if pattern.is_some_and(|pattern| line.contains(pattern)) {
+        ready.send_replace(true);
+    }
 }
 
-impl SidecarManager {
-    /// Create a new sidecar manager with the specified port.
-    pub fn new(port: u16) -> Self {
-        Self { child: None, port }
+/// Emit `event` to the window labelled `target`, or to every window.
+fn emit<S: Serialize + Clone>(
+    app: &AppHandle,
+    target: Option<&str>,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    match target {
+        Some(label) => app.emit_to(label, event, payload),
+        None => app.emit(event, payload),
     }
+}
 
-    /// Get the port the sidecar is running on.
-    pub fn port(&self) -> u16 {
-        self.port
+/// Handles the output of a piped sidecar until it exits.
+struct OutputTask {
+    name: String,
+    /// Prefix of the lines echoed to the console.
+    console: String,
+    forwarder: LogForwarder,
+    serving: watch::Receiver<bool>,
+    log_lines: broadcast::Sender<LogEntry>,
+    ready_tx: watch::Sender<bool>,
+    ready_pattern: Option<String>,
+    port_tx: Arc<watch::Sender<Option<u16>>>,
+    port_pattern: Option<Regex>,
+    log_filter: LogFilter,
+    log_levels: LogLevels,
+    reporter: ExitReporter,
+    pid: u32,
+}
+
+impl OutputTask {
+    async fn run(mut self, mut rx: mpsc::Receiver<CommandEvent>) {
+        let mut release_tick = tokio::time::interval(LOG_RELEASE_INTERVAL);
+        release_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut terminated = false;
+        loop {
+            let event = tokio::select! {
+                event = rx.recv() => event,
+                _ = self.serving.wait_for(|serving| *serving), if self.forwarder.holding() => {
+                    self.forwarder.release();
+                    continue;
+                }
+                _ = release_tick.tick(), if self.forwarder.releasing() => {
+                    self.forwarder.release_batch();
+                    continue;
+                }
+            };
+            let Some(event) = event else {
+                break;
+            };
+            match event {
+                CommandEvent::Stdout(line) => {
+                    let line = String::from_utf8_lossy(&line).into_owned();
+                    println!("{}: {}", self.console, line);
+                    self.line(LogStream::Stdout, LogLevel::Info, line);
+                }
+                CommandEvent::Stderr(line) => {
+                    let line = String::from_utf8_lossy(&line).into_owned();
+                    let level = self.log_levels.classify(LogStream::Stderr, &line);
+                    match level {
+                        LogLevel::Info => eprintln!("{}: {}", self.console, line),
+                        LogLevel::Warn => eprintln!("{} Warning: {}", self.console, line),
+                        LogLevel::Error => eprintln!("{} Error: {}", self.console, line),
+                    }
+                    self.line(LogStream::Stderr, level, line);
+                }
+                CommandEvent::Error(error) => error!("API Process Error: {}", error),
+                CommandEvent::Terminated(payload) => {
+                    terminated = true;
+                    self.reporter.report(payload);
+                }
+                _ => {}
+            }
+        }
+        // The shell plugin keeps the channel open until it has waited on
+        // the process, so a backend that closes its stdout keeps running
+        // here. If the channel still ends without an exit (waiting failed),
+        // the process may be alive, so watch its pid rather than report it
+        // gone.
+        if !terminated {
+            warn!(
+                "Lost the API server's output before it exited, watching pid {} instead",
+                self.pid
+            );
+            while process::is_alive(self.pid) {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            self.reporter.report(TerminatedPayload {
+                code: None,
+                signal: None,
+            });
+        }
     }
 
-    /// Start the sidecar process.
-    pub async fn start(&mut self, app: &AppHandle) -> Result<String, String> {
-        if self.child.is_some() {
-            return Ok("API server is already running".into());
+    fn line(&mut self, stream: LogStream, level: LogLevel, line: String) {
+        mark_ready(&self.ready_tx, self.ready_pattern.as_deref(), &line);
+        report_port(&self.port_tx, self.port_pattern.as_ref(), &line);
+        let allowed = self.log_filter.allows(&line);
+        let entry = LogEntry::new(&self.name, stream, level, line);
+        publish_line(&self.log_lines, &entry);
+        if allowed {
+            self.forwarder.record(entry);
         }
+    }
+}
 
-        println!("Starting API server on port {}...", self.port);
+/// Emits a log entry to the frontend.
+type LogSink = Box<dyn Fn(&LogEntry) + Send>;
 
-        let shell = app.shell();
-        let (mut rx, child) = shell
-            .sidecar("api")
-            .map_err(|e| format!("Failed to create sidecar command: {}", e))?
-            .args(["--port", &self.port.to_string()])
-            .spawn()
-            .map_err(|e| format!("Failed to spawn API server: {}", e))?;
-
-        // Spawn a task to handle sidecar output
-        tauri::async_runtime::spawn(async move {
-            while let Some(event) = rx.recv().await {
-                match event {
-                    CommandEvent::Stdout(line) => println!("API: {}", String::from_utf8_lossy(&line)),
-                    CommandEvent::Stderr(line) => {
-                        eprintln!("API Error: {}", String::from_utf8_lossy(&line))
-                    }
-                    CommandEvent::Error(error) => eprintln!("API Process Error: {}", error),
-                    CommandEvent::Terminated(status) => {
-                        println!("API Process Terminated with status: {:?}", status)
-                    }
-                    _ => {}
+/// Stores lines of sidecar output and forwards them to the frontend.
+struct LogForwarder {
+    sink: LogSink,
+    logs: Arc<StdMutex<LogBuffer>>,
+    /// Entries not emitted yet because the backend isn't ready, or `None`
+    /// once they are emitted as they come.
+    held: Option<VecDeque<LogEntry>>,
+    /// Set once the backend is ready, while `held` is emitted in batches.
+    releasing: bool,
+    dropped: usize,
+}
+
+impl LogForwarder {
+    /// With `hold`, entries are stored right away but only emitted on
+    /// [`release`](Self::release), so a backend flooding its output while
+    /// it boots doesn't flood the UI with events too.
+    fn new(
+        app: AppHandle,
+        target: Option<String>,
+        event: String,
+        logs: Arc<StdMutex<LogBuffer>>,
+        hold: bool,
+    ) -> Self {
+        let sink = Box::new(move |entry: &LogEntry| {
+            let _ = emit(&app, target.as_deref(), &event, entry);
+        });
+        Self::with_sink(sink, logs, hold)
+    }
+
+    fn with_sink(sink: LogSink, logs: Arc<StdMutex<LogBuffer>>, hold: bool) -> Self {
+        Self {
+            sink,
+            logs,
+            held: hold.then(VecDeque::new),
+            releasing: false,
+            dropped: 0,
+        }
+    }
+
+    /// Whether entries are held until the backend is ready.
+    fn holding(&self) -> bool {
+        self.held.is_some() && !self.releasing
+    }
+
+    /// Whether held entries are being emitted, see
+    /// [`release_batch`](Self::release_batch).
+    fn releasing(&self) -> bool {
+        self.releasing
+    }
+
+    /// Store a line and emit it, or hold it back until everything held is
+    /// released. Only the last [`MAX_HELD_LOG_EVENTS`] are held; all of
+    /// them are stored.
+    fn record(&mut self, entry: LogEntry) {
+        match &mut self.held {
+            Some(held) => {
+                if held.len() == MAX_HELD_LOG_EVENTS {
+                    held.pop_front();
+                    self.dropped += 1;
                 }
+                held.push_back(entry.clone());
             }
-        });
+            None => (self.sink)(&entry),
+        }
+        lock(&self.logs).push(entry);
+    }
 
-        self.child = Some(child);
-        println!("API server started successfully on port {}", self.port);
-        Ok(format!("API server started on port {}", self.port))
+    /// Start emitting what was held back, [`LOG_RELEASE_BATCH`] entries
+    /// per [`release_batch`](Self::release_batch), so the UI doesn't get
+    /// them all at once just as the backend becomes ready.
+    fn release(&mut self) {
+        if self.held.is_none() {
+            return;
+        }
+        if self.dropped > 0 {
+            debug!(
+                "Not emitting {} log lines from before the API server was ready",
+                self.dropped
+            );
+        }
+        self.releasing = true;
+    }
+
+    /// Emit the next batch of held entries, and emit new entries as they
+    /// come once none are left.
+    fn release_batch(&mut self) {
+        let Some(held) = &mut self.held else {
+            return;
+        };
+        let batch = held.len().min(LOG_RELEASE_BATCH);
+        for entry in held.drain(..batch) {
+            (self.sink)(&entry);
+        }
+        if held.is_empty() {
+            self.held = None;
+            self.releasing = false;
+        }
     }
+}
+
+/// Send SIGHUP to the sidecar. Returns whether it was delivered; always
+/// `false` on Windows, which has no equivalent.
+fn hangup_sidecar(pid: u32) -> bool {
+    info!("Sending SIGHUP to API server...");
+    process::hangup(pid)
+}
+
+/// Ask the sidecar to exit gracefully.
+fn terminate_sidecar(pid: u32) {
+    process::terminate(pid);
+}
+
+/// Kill the sidecar and any processes it spawned.
+fn kill_sidecar(child: SidecarChild) -> Result<(), String> {
+    // Kill child processes first
+    kill_children(child.pid());
 
-    /// Stop the sidecar process.
-    pub fn stop(&mut self) -> Result<String, String> {
-        if let Some(child) = self.child.take() {
-            println!("Stopping API server...");
+    // Kill the main process
+    match child {
+        SidecarChild::Piped(child) => child
+            .kill()
+            .map_err(|e| format!("Failed to stop API server: {}", e)),
+        SidecarChild::Inherited(pid) => {
+            process::kill(pid);
+            Ok(())
+        }
+    }
+}
 
-            let pid = child.pid();
+/// The shutdown steps of a manager, see [`SidecarManager::with_shutdown_steps`].
+#[derive(Clone)]
+struct ShutdownPlan {
+    steps: Vec<ShutdownStep>,
+    retries: (u32, Duration),
+}
 
-            // Kill child processes first
-            #[cfg(unix)]
-            {
-                let _ = StdCommand::new("pkill")
-                    .args(["-P", &pid.to_string()])
-                    .output();
+impl ShutdownPlan {
+    /// Walk the steps for `process`, which listens on `port`.
+    async fn run(
+        &self,
+        mut process: SidecarProcess,
+        port: u16,
+        reason: ShutdownReason,
+    ) -> Result<ShutdownReport, String> {
+        process.stopping.store(true, Ordering::Relaxed);
+        let started = Instant::now();
+        let mut report = ShutdownReport {
+            steps: Vec::new(),
+            terminated_by: "already_exited",
+            total_ms: 0,
+            shutdown_requests: 0,
+        };
+        let finish = |mut report: ShutdownReport, step: Option<&'static str>| {
+            if let Some(step) = step {
+                report.terminated_by = step;
+            }
+            report.total_ms = started.elapsed().as_millis() as u64;
+            report
+        };
+        for step in &self.steps {
+            if process.wait_for_exit(Duration::ZERO).await {
+                return Ok(finish(report, None));
+            }
+            if *step == ShutdownStep::Sigkill {
+                break;
             }
 
-            #[cfg(windows)]
-            {
-                let _ = StdCommand::new("taskkill")
-                    .args(["/F", "/T", "/PID", &pid.to_string()])
-                    .output();
+            let step_started = Instant::now();
+            let exited = match step {
+                ShutdownStep::HttpShutdown { path, grace } => {
+                    let (retries, delay) = self.retries;
+                    let mut accepted = false;
+                    for attempt in 0..=retries {
+                        if attempt > 0 {
+                            tokio::time::sleep(delay).await;
+                        }
+                        info!("Requesting API server shutdown via POST {}...", path);
+                        report.shutdown_requests += 1;
+                        let body = serde_json::json!({ "reason": reason }).to_string();
+                        let headers = [("Content-Type", "application/json")];
+                        let request = http::request(
+                            port,
+                            "POST",
+                            path,
+                            &headers,
+                            Some(body.as_bytes()),
+                            Duration::from_secs(2),
+                        );
+                        match request.await {
+                            Ok(response) if (200..300).contains(&response.status) => {
+                                accepted = true;
+                                break;
+                            }
+                            Ok(response) => {
+                                warn!("HTTP shutdown request returned {}", response.status)
+                            }
+                            Err(e) => warn!("HTTP shutdown request failed: {}", e),
+                        }
+                    }
+                    accepted && process.wait_for_exit(*grace).await
+                }
+                ShutdownStep::Sigterm { grace } => {
+                    info!("Sending SIGTERM to API server...");
+                    terminate_sidecar(process.child.pid());
+                    process.wait_for_exit(*grace).await
+                }
+                ShutdownStep::Sigkill => false,
+            };
+            report.steps.push(ShutdownStepTiming {
+                step: step.name(),
+                elapsed_ms: step_started.elapsed().as_millis() as u64,
+            });
+            if exited {
+                return Ok(finish(report, Some(step.name())));
             }
+        }
 
-            // Kill the main process
-            child
-                .kill()
-                .map_err(|e| format!("Failed to stop API server: {}", e))?;
+        let step_started = Instant::now();
+        let mut exited = process.exited.clone();
+        kill_sidecar(process.child)?;
+        // The exit is only reported once the child has been waited on, so
+        // this also makes sure it doesn't linger as a zombie
+        if tokio::time::timeout(REAP_TIMEOUT, exited.wait_for(|exited| *exited))
+            .await
+            .is_err()
+        {
+            warn!(
+                "API server was killed but not reaped within {:?}",
+                REAP_TIMEOUT
+            );
+        }
+        report.steps.push(ShutdownStepTiming {
+            step: ShutdownStep::Sigkill.name(),
+            elapsed_ms: step_started.elapsed().as_millis() as u64,
+        });
+        Ok(finish(report, Some(ShutdownStep::Sigkill.name())))
+    }
+}
 
-            println!("API server stopped");
-            Ok("API server stopped".into())
+/// Kill a spawned process that is being given up on before it was put to
+/// use, and wait for it to be reaped.
+async fn discard_process(process: SidecarProcess) {
+    process.stopping.store(true, Ordering::Relaxed);
+    let mut exited = process.exited.clone();
+    let _ = kill_sidecar(process.child);
+    let _ = tokio::time::timeout(REAP_TIMEOUT, exited.wait_for(|exited| *exited)).await;
+}
+
+/// Wait up to [`REAP_TIMEOUT`] for the sidecar behind `exited`, which
+/// [`SidecarManager::stop`] killed, to be reaped, warning if it isn't and,
+/// in debug builds, if `port` is still held afterwards. Both spawn modes
+/// wait on the child before reporting its exit, so once it is reported it
+/// is no zombie.
+async fn report_reaped(mut exited: watch::Receiver<bool>, port: u16) {
+    // A dropped sender means the output task is gone, so it counts too
+    let reaped = tokio::time::timeout(REAP_TIMEOUT, exited.wait_for(|exited| *exited)).await;
+    if reaped.is_err() {
+        warn!(
+            "API server was killed but not reaped within {:?}",
+            REAP_TIMEOUT
+        );
+        return;
+    }
+    warn_port_held(port);
+}
+
+/// Check that `port` can be bound, see
+/// [`SidecarManager::check_port_released`].
+fn port_released(port: u16) -> Result<(), SidecarError> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map(drop)
+        .map_err(|e| format!("Port {} is still held: {}", port, e).into())
+}
+
+/// In debug builds, warn if `port` is still held after a stop.
+fn warn_port_held(port: u16) {
+    if cfg!(debug_assertions) {
+        if let Err(e) = port_released(port) {
+            warn!("{} after stopping the API server", e);
+        }
+    }
+}
+
+/// Kill the processes spawned by `pid` (on Windows, `pid` included).
+fn kill_children(pid: u32) {
+    process::kill_children(pid);
+}
+
+/// Kill the process tree of `pid` without its [`CommandChild`], e.g. after
+/// a wedged shutdown took the handle with it.
+pub(crate) fn force_kill(pid: u32) {
+    process::kill_children(pid);
+    process::kill(pid);
+}
+
+/// Environment of a command, see [`SidecarManager::command_env`].
+struct CommandEnv {
+    /// Whether to start from an empty environment instead of the app's.
+    clear: bool,
+    /// Set in order, so a later one wins.
+    vars: Vec<(OsString, OsString)>,
+}
+
+/// Why [`SidecarManager::spawn`] failed.
+struct SpawnError {
+    message: String,
+    /// Whether trying again shortly may succeed.
+    transient: bool,
+}
+
+impl From<String> for SpawnError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            transient: false,
+        }
+    }
+}
+
+/// Whether a spawn error is likely to clear up by itself: the system is
+/// briefly out of processes, or (on Windows) antivirus still has the binary
+/// open. A missing binary is never transient.
+fn is_transient(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) {
+        return true;
+    }
+    #[cfg(unix)]
+    let transient = [libc::EAGAIN, libc::EINTR, libc::ETXTBSY];
+    // ERROR_ACCESS_DENIED and ERROR_SHARING_VIOLATION
+    #[cfg(windows)]
+    let transient = [5, 32];
+    e.raw_os_error()
+        .is_some_and(|code| transient.contains(&code))
+}
+
+/// Records how a sidecar exited and, for unexpected exits, counts the crash
+/// and wakes the health monitor.
+struct ExitReporter {
+    stopping: Arc<AtomicBool>,
+    awaited: Arc<AtomicBool>,
+    crash_count: Arc<AtomicU32>,
+    crashed: Arc<Notify>,
+    last_exit: Arc<StdMutex<Option<SidecarExit>>>,
+    exit_messages: HashMap<i32, String>,
+    lifecycle: Lifecycle,
+    exited_tx: watch::Sender<bool>,
+}
+
+impl ExitReporter {
+    fn report(&self, payload: TerminatedPayload) {
+        let exit = SidecarExit::new(
+            payload,
+            self.stopping.load(Ordering::Relaxed),
+            self.awaited.load(Ordering::Relaxed),
+        );
+        let description = match lookup_exit_message(&self.exit_messages, &exit) {
+            Some(message) => format!("{}: {}", exit, message),
+            None => exit.to_string(),
+        };
+        if exit.clean {
+            info!("API Process {}", description);
         } else {
-            Ok("API server is not running".into())
+            warn!("API Process {}", description);
+        }
+        *lock(&self.last_exit) = Some(exit);
+        let _ = self.exited_tx.send(true);
+        if !exit.clean {
+            self.crash_count.fetch_add(1, Ordering::Relaxed);
+            let _ = self.lifecycle.send(LifecycleEvent::Failed(format!(
+                "API server {}",
+                description
+            )));
+            self.crashed.notify_one();
         }
     }
+}
+
+/// Wait on an inherited-stdio sidecar from a thread of its own, reporting
+/// its exit once it has been reaped.
+fn wait_inherited(mut child: std::process::Child, reporter: ExitReporter) -> SidecarChild {
+    let pid = child.id();
+    std::thread::spawn(move || {
+        let payload = match child.wait() {
+            Ok(status) => terminated_payload(status),
+            Err(e) => {
+                error!("API Process Error: {}", e);
+                TerminatedPayload {
+                    code: None,
+                    signal: None,
+                }
+            }
+        };
+        reporter.report(payload);
+    });
+    SidecarChild::Inherited(pid)
+}
 
-    /// Restart the sidecar process.
-    pub async fn restart(&mut self) -> Result<String, String> {
-        // We can't restart without an app handle, so this is a placeholder
-        // In practice, you'd store the app handle or use a different approach
-        self.stop()?;
-        Err("Restart requires app handle - use Tauri commands".into())
+/// The message in `messages` for `exit`'s code, unless it was a clean exit.
+fn lookup_exit_message(messages: &HashMap<i32, String>, exit: &SidecarExit) -> Option<String> {
+    if exit.clean {
+        return None;
     }
+    messages.get(&exit.code?).cloned()
 }
 
-impl Drop for SidecarManager {
-    fn drop(&mut self) {
-        let _ = self.stop();
+/// Convert the exit status of an inherited-stdio sidecar to the payload
+/// the shell plugin reports for piped ones.
+fn terminated_payload(status: ExitStatus) -> TerminatedPayload {
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+    #[cfg(windows)]
+    let signal = None;
+    TerminatedPayload {
+        code: status.code(),
+        signal,
+    }
+}
+
+/// Emit `event` once the backend on `port` accepts a TCP connection, so the
+/// UI can tell a backend that is slow to bind from one that is slow to
+/// initialize. Gives up once it exits or after `timeout`.
+async fn announce_connecting(
+    app: AppHandle,
+    target: Option<String>,
+    event: String,
+    mut port: watch::Receiver<Option<u16>>,
+    timeout: Duration,
+    mut exited: watch::Receiver<bool>,
+) {
+    let connect = async {
+        let port = assigned_port(&mut port, timeout).await.ok()?;
+        while TcpStream::connect(("127.0.0.1", port)).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Some(port)
+    };
+    let port = tokio::select! {
+        result = tokio::time::timeout(timeout, connect) => result.ok().flatten(),
+        _ = exited.wait_for(|exited| *exited) => None,
+    };
+    if let Some(port) = port {
+        let _ = emit(&app, target.as_deref(), &event, BackendConnecting { port });
+    }
+}
+
+/// Emit [`StartupProgress`] every [`STARTUP_PROGRESS_INTERVAL`] until the
+/// backend is serving or exits, estimating against `typical`.
+async fn announce_startup_progress(
+    app: AppHandle,
+    target: Option<String>,
+    event: String,
+    typical: Duration,
+    mut serving: watch::Receiver<bool>,
+    mut exited: watch::Receiver<bool>,
+) {
+    let started = Instant::now();
+    loop {
+        let percent = startup_percent(started.elapsed(), typical);
+        let _ = emit(&app, target.as_deref(), &event, StartupProgress { percent });
+        tokio::select! {
+            _ = tokio::time::sleep(STARTUP_PROGRESS_INTERVAL) => {}
+            _ = serving.wait_for(|serving| *serving) => return,
+            _ = exited.wait_for(|exited| *exited) => return,
+        }
+    }
+}
+
+/// Progress after `elapsed` of a startup that typically takes `typical`:
+/// linear up to 90% at the typical time, then creeping towards 99%.
+fn startup_percent(elapsed: Duration, typical: Duration) -> u8 {
+    let ratio = elapsed.as_secs_f64() / typical.as_secs_f64().max(0.001);
+    let percent = if ratio <= 1.0 {
+        90.0 * ratio
+    } else {
+        90.0 + 9.0 * (1.0 - 1.0 / ratio)
+    };
+    percent.clamp(0.0, 99.0) as u8
+}
+
+/// The typical startup time stored at `path`, if any.
+fn read_typical_startup(path: &Path) -> Option<Duration> {
+    let ms = std::fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(Duration::from_millis(ms))
+}
+
+/// Blend a startup that took `took` into the typical startup time at
+/// `path`, so one slow launch doesn't throw off the next estimate.
+fn record_startup(path: &Path, previous: Option<Duration>, took: Duration) {
+    let typical = match previous {
+        Some(previous) => {
+            previous.mul_f64(1.0 - STARTUP_HISTORY_WEIGHT) + took.mul_f64(STARTUP_HISTORY_WEIGHT)
+        }
+        None => took,
+    };
+    if let Err(e) = std::fs::write(path, typical.as_millis().to_string()) {
+        warn!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Wait up to `timeout` for a sidecar's port to be known.
+async fn assigned_port(
+    port: &mut watch::Receiver<Option<u16>>,
+    timeout: Duration,
+) -> Result<u16, String> {
+    match tokio::time::timeout(timeout, port.wait_for(Option::is_some)).await {
+        Ok(Ok(port)) => (*port).ok_or_else(|| "no port reported".to_string()),
+        Ok(Err(_)) => Err("exited before reporting its port".into()),
+        Err(_) => Err(format!("no port reported after {:?}", timeout)),
+    }
+}
+
+/// Report the port captured by `pattern` from `line`, unless one is known
+/// already.
+fn report_port(port: &watch::Sender<Option<u16>>, pattern: Option<&Regex>, line: &str) {
+    let Some(pattern) = pattern else {
+        return;
+    };
+    if port.borrow().is_some() {
+        return;
+    }
+    if let Some(found) = pattern
+        .captures(line)
+        .and_then(|captures| captures.get(1)?.as_str().parse::<u16>().ok())
+    {
+        debug!("Backend reported port {}", found);
+        port.send_replace(Some(found));
+    }
+}
+
+/// Poll `path` until the backend has written its port to it.
+async fn read_port_file(
+    path: PathBuf,
+    port: Arc<watch::Sender<Option<u16>>>,
+    timeout: Duration,
+    exited: watch::Receiver<bool>,
+) {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline && !*exited.borrow() {
+        if let Some(found) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| contents.trim().parse::<u16>().ok())
+            .filter(|&found| found != 0)
+        {
+            debug!("Backend reported port {} in {}", found, path.display());
+            port.send_replace(Some(found));
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// Wait up to `timeout` for something to accept connections on `port`, e.g.
+/// a [named port](SidecarManager::port_named), without holding the manager.
+pub async fn wait_for_port(port: u16, timeout: Duration) -> Result<(), SidecarError> {
+    wait_until_listening(port, timeout, Duration::ZERO, 1, &Arc::default())
+        .await
+        .map_err(SidecarError::from)
+}
+
+/// Wait up to `timeout` for a line containing `pattern` on `logs`, from
+/// [`SidecarManager::subscribe_logs`], e.g. "Migrations complete" before
+/// running a test against the backend. Only lines after the subscription
+/// count; lines missed by falling behind are skipped.
+pub async fn wait_for_log(
+    mut logs: broadcast::Receiver<LogEntry>,
+    pattern: &str,
+    timeout: Duration,
+) -> Result<LogEntry, SidecarError> {
+    let found = tokio::time::timeout(timeout, async {
+        loop {
+            match logs.recv().await {
+                Ok(entry) if entry.text.contains(pattern) => return Ok(entry),
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err(SidecarError::from("The manager was dropped"))
+                }
+            }
+        }
+    });
+    found
+        .await
+        .map_err(|_| format!("No log line containing {:?} after {:?}", pattern, timeout))?
+}
+
+/// Poll until something accepts TCP connections on `port`, keeping up to
+/// `probes` connection attempts in flight. Failures during the first `quiet`
+/// are expected while the backend boots and only logged at trace level.
+async fn wait_until_listening(
+    port: u16,
+    timeout: Duration,
+    quiet: Duration,
+    probes: usize,
+    counters: &Arc<Counters>,
+) -> Result<(), String> {
+    let started = tokio::time::Instant::now();
+    let deadline = started + timeout;
+    let quiet_until = started + quiet;
+    let (tx, mut rx) = mpsc::channel(probes);
+    let mut in_flight = 0;
+    let mut warned = quiet.is_zero();
+    loop {
+        while in_flight < probes {
+            let tx = tx.clone();
+            let counters = counters.clone();
+            tauri::async_runtime::spawn(async move {
+                let connected = matches!(
+                    tokio::time::timeout(
+                        Duration::from_secs(2),
+                        TcpStream::connect(("127.0.0.1", port))
+                    )
+                    .await,
+                    Ok(Ok(_))
+                );
+                if connected || tokio::time::Instant::now() >= quiet_until {
+                    debug!("Readiness probe on port {}: connected={}", port, connected);
+                } else {
+                    trace!("Readiness probe on port {}: connected={}", port, connected);
+                }
+                counters.record_probe(connected);
+                if !connected {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                let _ = tx.send(connected).await;
+            });
+            in_flight += 1;
+        }
+
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(true)) => return Ok(()),
+            Ok(_) => {
+                in_flight -= 1;
+                if !warned && tokio::time::Instant::now() >= quiet_until {
+                    warned = true;
+                    warn!(
+                        "Nothing is accepting connections on port {} after {:?}, still waiting",
+                        port, quiet
+                    );
+                }
+            }
+            Err(_) => {
+                return Err(format!(
+                    "no connection on port {} after {:?}",
+                    port, timeout
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::{self, ProxyRequest};
+
+    /// Track a process spawned from `command` as the manager's sidecar, the
+    /// way `spawn` does with inherited stdio, since spawning through the
+    /// shell plugin needs a running app. Returns its pid.
+    #[cfg(unix)]
+    fn track(manager: &mut SidecarManager, command: &mut StdCommand) -> u32 {
+        let (exited_tx, exited) = watch::channel(false);
+        let stopping = Arc::new(AtomicBool::new(false));
+        let awaited = Arc::new(AtomicBool::new(false));
+        let reporter = manager.exit_reporter(&stopping, &awaited, exited_tx);
+        let child = command.spawn().expect("failed to spawn test process");
+        let child = wait_inherited(child, reporter);
+        let pid = child.pid();
+        let (port_tx, port) = watch::channel(Some(manager.port()));
+        manager.intentionally_stopped = false;
+        manager.child = Some(SidecarProcess {
+            child,
+            exited,
+            stopping,
+            awaited,
+            ready: None,
+            port,
+            _port_tx: Arc::new(port_tx),
+            started_at: Instant::now(),
+            started_ms: now_ms(),
+            _cgroup: None,
+            instance_id: None,
+            compromised: false,
+        });
+        let _ = manager.lifecycle.send(LifecycleEvent::Started);
+        pid
+    }
+
+    /// An output task for `pid` that records the log events it emits in
+    /// `emitted`, with the exit it reports.
+    fn output_task(
+        manager: &SidecarManager,
+        pid: u32,
+        serving: watch::Receiver<bool>,
+        emitted: Arc<StdMutex<Vec<(Instant, LogEntry)>>>,
+    ) -> (OutputTask, watch::Receiver<bool>) {
+        let (exited_tx, exited) = watch::channel(false);
+        let reporter = manager.exit_reporter(&Arc::default(), &Arc::default(), exited_tx);
+        let sink = Box::new(move |entry: &LogEntry| {
+            lock(&emitted).push((Instant::now(), entry.clone()));
+        });
+        let hold = !*serving.borrow();
+        let task = OutputTask {
+            name: DEFAULT_NAME.into(),
+            console: "API".into(),
+            forwarder: LogForwarder::with_sink(sink, manager.logs.clone(), hold),
+            serving,
+            log_lines: manager.log_lines.clone(),
+            ready_tx: watch::channel(false).0,
+            ready_pattern: None,
+            port_tx: Arc::new(watch::channel(None).0),
+            port_pattern: None,
+            log_filter: LogFilter::default(),
+            log_levels: LogLevels::default(),
+            reporter,
+            pid,
+        };
+        (task, exited)
+    }
+
+    /// How a [`FakeBackend`] answers each request.
+    #[derive(Debug, Clone, Copy)]
+    enum Answer {
+        /// `200 OK` with body `ok`.
+        Healthy,
+        /// `503 Service Unavailable`.
+        Unhealthy,
+        /// [`Healthy`](Self::Healthy), after a delay.
+        Slow(Duration),
+        /// Close the connection without a response.
+        Closing,
+    }
+
+    /// A backend on a free localhost port, answering every request on a
+    /// thread of its own. The heads of the requests it got are kept in
+    /// `requests`.
+    struct FakeBackend {
+        port: u16,
+        requests: Arc<StdMutex<Vec<String>>>,
+    }
+
+    impl FakeBackend {
+        fn start(answer: Answer) -> Self {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let requests = Arc::new(StdMutex::new(Vec::new()));
+            let received = requests.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let received = received.clone();
+                    std::thread::spawn(move || Self::answer(stream, answer, &received));
+                }
+            });
+            Self { port, requests }
+        }
+
+        fn answer(mut stream: StdTcpStream, answer: Answer, received: &StdMutex<Vec<String>>) {
+            use std::io::{Read, Write};
+
+            // The head, then as much body as it announces
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            let head_end = loop {
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end;
+                }
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            };
+            let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+            let body_len: usize = head
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse().ok())
+                .unwrap_or(0);
+            while request.len() < head_end + 4 + body_len {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            lock(received).push(head);
+
+            let response: &[u8] = match answer {
+                Answer::Healthy => b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+                Answer::Unhealthy => {
+                    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n"
+                }
+                Answer::Slow(delay) => {
+                    std::thread::sleep(delay);
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok"
+                }
+                Answer::Closing => return,
+            };
+            let _ = stream.write_all(response);
+        }
+    }
+
+    /// A process that runs until it is killed.
+    #[cfg(unix)]
+    fn sleeper() -> StdCommand {
+        let mut command = StdCommand::new("sleep");
+        command.arg("30");
+        command
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stop_releases_a_port_held_by_the_sidecar() {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::process::CommandExt;
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let fd = listener.as_raw_fd();
+        let mut command = sleeper();
+        // Leave the listening socket open in the child only, so it holds
+        // the port the way a backend bound to it would.
+        // SAFETY: fcntl(2) is async-signal-safe and `fd` outlives the spawn.
+        unsafe {
+            command.pre_exec(move || {
+                if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut manager = SidecarManager::new(port);
+        track(&mut manager, &mut command);
+        drop(listener);
+        assert!(manager.check_port_released().is_err());
+
+        manager.stop().unwrap();
+        tauri::async_runtime::block_on(manager.wait_reaped());
+        manager.check_port_released().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_panic_while_holding_a_lock_does_not_wedge_the_manager() {
+        let mut manager = SidecarManager::new(portpicker::pick_unused_port().unwrap());
+        let logs = manager.logs.clone();
+        let last_exit = manager.last_exit.clone();
+        let _ = std::thread::spawn(move || {
+            let _logs = logs.lock().unwrap();
+            let _last_exit = last_exit.lock().unwrap();
+            panic!("injected panic while holding the log and exit locks");
+        })
+        .join();
+        assert!(manager.logs.is_poisoned());
+        assert!(manager.last_exit.is_poisoned());
+
+        for _ in 0..2 {
+            track(&mut manager, &mut sleeper());
+            assert_eq!(manager.status().status, SidecarStatus::Running);
+            manager.stop().unwrap();
+            assert_eq!(manager.status().status, SidecarStatus::Stopped);
+            tauri::async_runtime::block_on(manager.wait_reaped());
+            assert!(manager.last_exit().is_some_and(|exit| exit.clean));
+            manager.recent_logs(None, None, false);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn duplicate_and_concurrent_stops_report_the_stop_once() {
+        let mut manager = SidecarManager::new(portpicker::pick_unused_port().unwrap());
+        let mut events = manager.subscribe();
+
+        track(&mut manager, &mut sleeper());
+        assert_eq!(manager.stop().unwrap(), "API server stopped");
+        assert_eq!(manager.stop().unwrap(), "API server is already stopped");
+
+        track(&mut manager, &mut sleeper());
+        let manager = Mutex::new(manager);
+        let (first, second) = tauri::async_runtime::block_on(async {
+            tokio::join!(async { manager.lock().await.stop_async().await }, async {
+                manager.lock().await.stop()
+            },)
+        });
+        let mut messages = [first.unwrap(), second.unwrap()];
+        messages.sort();
+        assert_eq!(
+            messages,
+            ["API server is already stopped", "API server stopped"]
+        );
+
+        // One per process that was running
+        let stops = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|event| matches!(event, LifecycleEvent::Stopped(_)))
+            .count();
+        assert_eq!(stops, 2);
+        let manager = manager.into_inner();
+        let stopped = manager
+            .status_history()
+            .into_iter()
+            .filter(|transition| transition.to == SidecarStatus::Stopped)
+            .count();
+        assert_eq!(stopped, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stop_reaps_the_killed_process() {
+        let mut manager = SidecarManager::new(portpicker::pick_unused_port().unwrap());
+        let pid = track(&mut manager, &mut sleeper());
+        manager.stop().unwrap();
+        tauri::async_runtime::block_on(manager.wait_reaped());
+
+        // An unreaped zombie would be reaped here instead.
+        // SAFETY: waitpid(2) accepts a null status pointer.
+        let waited =
+            unsafe { libc::waitpid(pid as libc::pid_t, std::ptr::null_mut(), libc::WNOHANG) };
+        assert_eq!(waited, -1);
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error(),
+            Some(libc::ECHILD)
+        );
+    }
+
+    #[test]
+    fn logs_flooded_before_the_port_binds_are_released_in_batches() {
+        let manager = SidecarManager::new(portpicker::pick_unused_port().unwrap());
+        let (serving_tx, serving) = watch::channel(false);
+        let emitted = Arc::new(StdMutex::new(Vec::new()));
+        let (task, _exited) = output_task(&manager, 0, serving, emitted.clone());
+        let (tx, rx) = mpsc::channel(1);
+
+        tauri::async_runtime::block_on(async {
+            let output = tauri::async_runtime::spawn(task.run(rx));
+            for i in 0..10_000 {
+                let line = format!("line {}", i).into_bytes();
+                tx.send(CommandEvent::Stdout(line)).await.unwrap();
+            }
+            tokio::time::sleep(LOG_RELEASE_INTERVAL).await;
+            assert!(lock(&emitted).is_empty());
+
+            serving_tx.send_replace(true);
+            let released = async {
+                while lock(&emitted).len() < MAX_HELD_LOG_EVENTS {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            };
+            tokio::time::timeout(Duration::from_secs(10), released)
+                .await
+                .unwrap();
+
+            let line = b"after release".to_vec();
+            tx.send(CommandEvent::Stdout(line)).await.unwrap();
+            tx.send(CommandEvent::Terminated(TerminatedPayload {
+                code: Some(0),
+                signal: None,
+            }))
+            .await
+            .unwrap();
+            drop(tx);
+            output.await.unwrap();
+        });
+
+        let emitted = lock(&emitted);
+        let texts: Vec<&str> = emitted
+            .iter()
+            .map(|(_, entry)| entry.text.as_str())
+            .collect();
+        let expected: Vec<String> = (10_000 - MAX_HELD_LOG_EVENTS..10_000)
+            .map(|i| format!("line {}", i))
+            .collect();
+        assert_eq!(texts[..MAX_HELD_LOG_EVENTS], expected);
+        assert_eq!(texts[MAX_HELD_LOG_EVENTS..], ["after release"]);
+        // Batches are at least an interval apart
+        let batches = MAX_HELD_LOG_EVENTS.div_ceil(LOG_RELEASE_BATCH) as u32;
+        let spread = emitted[MAX_HELD_LOG_EVENTS - 1].0 - emitted[0].0;
+        assert!(spread >= LOG_RELEASE_INTERVAL * (batches - 1));
+
+        let stored = lock(&manager.logs).recent(usize::MAX, None);
+        assert_eq!(stored.last().unwrap().text, "after release");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lost_output_is_not_reported_as_an_exit_while_the_process_lives() {
+        let manager = SidecarManager::new(portpicker::pick_unused_port().unwrap());
+        let mut child = sleeper().spawn().unwrap();
+        let (_serving_tx, serving) = watch::channel(true);
+        let (task, mut exited) = output_task(&manager, child.id(), serving, Arc::default());
+        // The output channel ends without a termination event
+        let (tx, rx) = mpsc::channel(1);
+        drop(tx);
+
+        tauri::async_runtime::block_on(async {
+            let output = tauri::async_runtime::spawn(task.run(rx));
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            assert!(!*exited.borrow());
+
+            child.kill().unwrap();
+            child.wait().unwrap();
+            tokio::time::timeout(Duration::from_secs(5), exited.wait_for(|exited| *exited))
+                .await
+                .unwrap()
+                .unwrap();
+            output.await.unwrap();
+        });
+        assert!(manager.last_exit().is_some_and(|exit| exit.code.is_none()));
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn arbitrary_output_is_stored_within_the_caps(
+            lines in proptest::collection::vec(
+                (proptest::bool::ANY, proptest::collection::vec(proptest::num::u8::ANY, 0..4096)),
+                0..64,
+            ),
+            giant in (proptest::num::u8::ANY, 0..(256 * 1024usize)),
+            capacity in 0..32usize,
+            byte_budget in 0..(64 * 1024usize),
+        ) {
+            let manager = SidecarManager::new(0)
+                .with_log_capacity(capacity)
+                .with_log_byte_budget(byte_budget);
+            let (_serving_tx, serving) = watch::channel(true);
+            let (task, _exited) = output_task(&manager, 0, serving, Arc::default());
+            let (tx, rx) = mpsc::channel(1);
+
+            let (byte, len) = giant;
+            let lines = lines.into_iter().chain([(false, vec![byte; len])]);
+            let finished = tauri::async_runtime::block_on(async {
+                let output = tauri::async_runtime::spawn(task.run(rx));
+                for (stderr, line) in lines {
+                    let event = if stderr {
+                        CommandEvent::Stderr(line)
+                    } else {
+                        CommandEvent::Stdout(line)
+                    };
+                    tx.send(event).await.unwrap();
+                }
+                tx.send(CommandEvent::Terminated(TerminatedPayload {
+                    code: Some(0),
+                    signal: None,
+                }))
+                .await
+                .unwrap();
+                drop(tx);
+                tokio::time::timeout(Duration::from_secs(10), output).await
+            });
+            proptest::prop_assert!(matches!(finished, Ok(Ok(()))));
+
+            let stored = lock(&manager.logs).recent(usize::MAX, None);
+            proptest::prop_assert!(stored.len() <= capacity);
+            let bytes: usize = stored.iter().map(|entry| entry.text.len()).sum();
+            proptest::prop_assert!(bytes <= byte_budget);
+        }
+    }
+
+    #[test]
+    fn probe_latency_is_only_reported_for_a_healthy_answer_in_time() {
+        let latency = |answer| {
+            let backend = FakeBackend::start(answer);
+            let manager = SidecarManager::new(backend.port);
+            tauri::async_runtime::block_on(manager.probe_latency_ms())
+        };
+
+        assert!(latency(Answer::Healthy).is_some());
+        assert!(latency(Answer::Slow(Duration::from_millis(200))).is_some_and(|ms| ms >= 200));
+        assert_eq!(latency(Answer::Unhealthy), None);
+        assert_eq!(latency(Answer::Closing), None);
+        // Longer than the probe's own timeout
+        assert_eq!(latency(Answer::Slow(Duration::from_millis(1500))), None);
+    }
+
+    #[test]
+    fn probe_latency_accepts_the_configured_health_statuses() {
+        let backend = FakeBackend::start(Answer::Unhealthy);
+        let manager = SidecarManager::new(backend.port).with_health_statuses([503]);
+        let latency = tauri::async_runtime::block_on(manager.probe_latency_ms());
+
+        assert!(latency.is_some());
+        let requests = lock(&backend.requests);
+        assert!(requests[0].starts_with("GET /health "), "{}", requests[0]);
+    }
+
+    /// How many times the unhealthy hook ran after `ticks` health checks of
+    /// a running sidecar whose backend answers with `answer`.
+    #[cfg(unix)]
+    fn unhealthy_hook_runs(answer: Answer, ticks: u32) -> usize {
+        let backend = FakeBackend::start(answer);
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted = runs.clone();
+        let mut manager = SidecarManager::new(backend.port).with_on_unhealthy(
+            UnhealthyAction::RunHook(Arc::new(move |unhealthy| {
+                assert_eq!(unhealthy.failed_probes, UNHEALTHY_AFTER_FAILURES);
+                counted.fetch_add(1, Ordering::Relaxed);
+            })),
+        );
+        manager.startup_timeout = Duration::ZERO;
+        track(&mut manager, &mut sleeper());
+
+        tauri::async_runtime::block_on(async {
+            for _ in 0..ticks {
+                manager.check_health().await;
+            }
+        });
+        manager.stop().unwrap();
+        runs.load(Ordering::Relaxed)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_health_runs_the_unhealthy_action_after_repeated_failures() {
+        assert_eq!(
+            unhealthy_hook_runs(Answer::Healthy, UNHEALTHY_AFTER_FAILURES),
+            0
+        );
+        assert_eq!(
+            unhealthy_hook_runs(Answer::Unhealthy, UNHEALTHY_AFTER_FAILURES - 1),
+            0
+        );
+        assert_eq!(
+            unhealthy_hook_runs(Answer::Unhealthy, UNHEALTHY_AFTER_FAILURES),
+            1
+        );
+        assert_eq!(
+            unhealthy_hook_runs(Answer::Closing, UNHEALTHY_AFTER_FAILURES * 2),
+            2
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_health_leaves_a_sidecar_in_its_startup_timeout_alone() {
+        let backend = FakeBackend::start(Answer::Unhealthy);
+        let mut manager = SidecarManager::new(backend.port).with_on_unhealthy(
+            UnhealthyAction::RunHook(Arc::new(|_| panic!("ran the hook"))),
+        );
+        track(&mut manager, &mut sleeper());
+
+        tauri::async_runtime::block_on(async {
+            for _ in 0..UNHEALTHY_AFTER_FAILURES {
+                manager.check_health().await;
+            }
+        });
+        manager.stop().unwrap();
+        assert!(lock(&backend.requests).is_empty());
+    }
+
+    #[test]
+    fn forward_passes_the_answer_through_with_the_token() {
+        let forward = |answer| {
+            let backend = FakeBackend::start(answer);
+            let request = ProxyRequest {
+                method: "post".into(),
+                path: "/items?page=2".into(),
+                headers: vec![("Content-Type".into(), "application/json".into())],
+                body: Some("{}".into()),
+            };
+            let response = tauri::async_runtime::block_on(proxy::forward(
+                backend.port,
+                Some("secret"),
+                DEFAULT_MAX_RESPONSE_BYTES,
+                &request,
+            ));
+            let requests = lock(&backend.requests).clone();
+            (response, requests)
+        };
+
+        let (response, requests) = forward(Answer::Healthy);
+        let response = response.unwrap();
+        assert_eq!((response.status, response.body.as_str()), (200, "ok"));
+        assert!(
+            requests[0].starts_with("POST /items?page=2 "),
+            "{}",
+            requests[0]
+        );
+        assert!(
+            requests[0].contains("Authorization: Bearer secret"),
+            "{}",
+            requests[0]
+        );
+
+        let (response, _) = forward(Answer::Unhealthy);
+        assert_eq!(response.unwrap().status, 503);
+        let (response, _) = forward(Answer::Slow(Duration::from_millis(200)));
+        assert_eq!(response.unwrap().body, "ok");
+        let (response, _) = forward(Answer::Closing);
+        assert!(response.is_err());
+    }
+
+    #[test]
+    fn maintenance_mode_is_a_status_with_its_own_transitions() {
+        let mut manager = SidecarManager::new(portpicker::pick_unused_port().unwrap());
+        tauri::async_runtime::block_on(manager.set_maintenance_mode(true));
+        assert_eq!(manager.status().status, SidecarStatus::Maintenance);
+        assert!(manager.running_port().is_err());
+        tauri::async_runtime::block_on(manager.set_maintenance_mode(false));
+        assert_eq!(manager.status().status, SidecarStatus::Stopped);
+
+        let transitions: Vec<_> = manager
+            .status_history()
+            .into_iter()
+            .map(|transition| (transition.from, transition.to))
+            .collect();
+        assert_eq!(
+            transitions,
+            [
+                (SidecarStatus::Stopped, SidecarStatus::Maintenance),
+                (SidecarStatus::Maintenance, SidecarStatus::Stopped),
+            ]
+        );
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn env_overrides_reject_an_out_of_range_startup_timeout() {
+        let error = EnvOverrides::read(env(&[("TETHER_STARTUP_TIMEOUT_MS", "5")]))
+            .unwrap_err()
+            .to_string();
+        let overrides = EnvOverrides::read(env(&[("TETHER_STARTUP_TIMEOUT_MS", "5000")])).unwrap();
+
+        assert!(error.contains("TETHER_STARTUP_TIMEOUT_MS"), "{}", error);
+        assert_eq!(overrides.startup_timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn env_overrides_reject_values_that_do_not_parse() {
+        for (name, value) in [
+            ("TETHER_PORT", "http"),
+            ("TETHER_PORT", "70000"),
+            ("TETHER_HEALTH_PATH", "health"),
+        ] {
+            let error = EnvOverrides::read(env(&[(name, value)]))
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains(name), "{}", error);
+        }
+        assert_eq!(
+            EnvOverrides::read(env(&[("TETHER_PORT", "")])).unwrap(),
+            EnvOverrides::default()
+        );
+    }
+
+    #[test]
+    fn a_preset_keeps_the_health_path_from_the_environment() {
+        let mut manager = SidecarManager::new(0);
+        manager.health_path = "/ready".to_string();
+        manager.env_overrides.health_path = Some("/ready".to_string());
+
+        let manager = manager.with_preset(SidecarPreset::FastApi);
+        assert_eq!(manager.health_path, "/ready");
+        let manager = manager.with_health_path("/live");
+        assert_eq!(manager.health_path, "/live");
     }
 }
@@ -0,0 +1,63 @@
+//! Forward termination signals sent to the app to the sidecars.
+//!
+//! Without this, a SIGTERM from a service manager (or Ctrl+C in the
+//! terminal running `tauri dev`) ends the app without running the window
+//! close handler, and the OS orphans the backend. Each sidecar decides what
+//! a signal means for it with
+//! [`with_signal_action`](crate::sidecar::SidecarManager::with_signal_action).
+//!
+//! On Windows only console control events (Ctrl+C, Ctrl+Break, console
+//! close) can be caught, and release builds have no console. A process
+//! killed via `TerminateProcess` (Task Manager, `taskkill /F`) gets no
+//! chance to clean up at all.
+
+use crate::registry::SidecarRegistry;
+use crate::sidecar::AppSignal;
+use log::{error, info};
+use tauri::{AppHandle, Manager};
+
+/// Wait for SIGTERM/SIGINT, dispatch it to the sidecars in the app's
+/// [`SidecarRegistry`], then exit the app.
+pub fn forward_to_sidecars(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let signal = match wait_for_exit_signal().await {
+            Ok(signal) => signal,
+            Err(e) => {
+                error!("Failed to install signal handler: {}", e);
+                return;
+            }
+        };
+
+        info!("Received {:?} signal, stopping sidecars...", signal);
+        if let Some(registry) = app.try_state::<SidecarRegistry>() {
+            registry.handle_signal(signal).await;
+        }
+        app.exit(0);
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_exit_signal() -> std::io::Result<AppSignal> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    Ok(tokio::select! {
+        _ = sigterm.recv() => AppSignal::Terminate,
+        _ = sigint.recv() => AppSignal::Interrupt,
+    })
+}
+
+#[cfg(windows)]
+async fn wait_for_exit_signal() -> std::io::Result<AppSignal> {
+    use tokio::signal::windows::{ctrl_break, ctrl_c, ctrl_close};
+
+    let mut ctrl_c = ctrl_c()?;
+    let mut ctrl_break = ctrl_break()?;
+    let mut ctrl_close = ctrl_close()?;
+    Ok(tokio::select! {
+        _ = ctrl_c.recv() => AppSignal::Interrupt,
+        _ = ctrl_break.recv() => AppSignal::Terminate,
+        _ = ctrl_close.recv() => AppSignal::Terminate,
+    })
+}
@@ -0,0 +1,52 @@
+//! Resource usage of the sidecar process.
+
+#[cfg(not(target_os = "linux"))]
+use std::process::Command as StdCommand;
+
+/// Resident set size of `pid` in bytes, if it can be determined.
+pub(crate) fn rss_bytes(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+        let kb = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kb * 1024)
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        let output = StdCommand::new("ps")
+            .args(["-o", "rss=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        let kb = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kb * 1024)
+    }
+
+    #[cfg(windows)]
+    {
+        // CSV columns: "Image Name","PID","Session Name","Session#","Mem Usage"
+        let output = StdCommand::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mem = stdout.trim().rsplit("\",\"").next()?;
+        let kb = mem
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>()
+            .parse::<u64>()
+            .ok()?;
+        Some(kb * 1024)
+    }
+}
@@ -0,0 +1,84 @@
+//! The directory disk-writing features (port files, persisted logs, crash
+//! dumps) write to.
+//!
+//! The app data directory isn't writable on every system, so it is probed
+//! once and callers skip writing when [`Storage::dir`] is `None`, instead of
+//! failing `start`.
+
+use log::warn;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// The app data directory and whether we can write to it.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    dir: Option<PathBuf>,
+    writable: bool,
+}
+
+impl Storage {
+    /// Check that the app data directory exists (creating it if needed) and
+    /// accepts writes. Warns once if it doesn't.
+    pub fn probe(app: &AppHandle) -> Self {
+        let dir = match app.path().app_data_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                warn!("no app data directory ({}), files will not be written", e);
+                return Self {
+                    dir: None,
+                    writable: false,
+                };
+            }
+        };
+
+        let probe = dir.join(".write-test");
+        let result = std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(&probe, b""))
+            .and_then(|_| std::fs::remove_file(&probe));
+        if let Err(e) = &result {
+            warn!(
+                "app data directory {} is not writable ({}), files will not be written",
+                dir.display(),
+                e
+            );
+        }
+
+        Self {
+            writable: result.is_ok(),
+            dir: Some(dir),
+        }
+    }
+
+    /// Directory to write files to, or `None` if it isn't writable.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref().filter(|_| self.writable)
+    }
+
+    /// Whether files can be written.
+    pub fn writable(&self) -> bool {
+        self.writable
+    }
+}
+
+/// The app log directory, created if needed, or `None` with a warning if it
+/// can't be.
+pub fn log_dir(app: &AppHandle) -> Option<PathBuf> {
+    let result = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| match std::fs::create_dir_all(&dir) {
+            Ok(()) => Ok(dir),
+            Err(e) => Err(format!("{}: {}", dir.display(), e)),
+        });
+    match result {
+        Ok(dir) => Some(dir),
+        Err(e) => {
+            warn!(
+                "app log directory is not available ({}), log files will not be written",
+                e
+            );
+            None
+        }
+    }
+}